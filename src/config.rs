@@ -0,0 +1,90 @@
+use crate::static_resources::{
+    B_DECR, B_INCR, C_INCR, EMARK_INCR, E_INCR, MAX_EMARK, MAX_QMARK, MAX_QMARK_INCR,
+    NEGATION_SCALAR, NORMALIZATION_ALPHA, QMARK_INCR,
+};
+
+/// Empirically derived constants used to scale and amplify sentiment.
+///
+/// The default values are the ones from the original VADER implementation. Use
+/// struct update syntax to only change some of them:
+///
+/// ```
+/// use vader_sentimental::{ScoringConfig, SentimentIntensityAnalyzer};
+///
+/// let config = ScoringConfig {
+///     normalization_alpha: 5.0,
+///     ..ScoringConfig::default()
+/// };
+/// let analyzer = SentimentIntensityAnalyzer::builder().scoring_config(config).build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ScoringConfig {
+    /// Valence added by boosters such as "very" in the built-in booster dictionary
+    pub b_incr: f64,
+    /// Valence added by dampeners such as "barely" in the built-in booster dictionary
+    pub b_decr: f64,
+    /// Emphasis of all caps words in text that is not all caps
+    pub c_incr: f64,
+    /// Emphasis of elongated words like "goooood", if enabled with `Elongation::Emphasize`
+    pub elongation_incr: f64,
+    /// Factor applied to negated words
+    pub negation_scalar: f64,
+    /// Emphasis per question mark
+    pub qmark_incr: f64,
+    /// Emphasis per exclamation mark
+    pub emark_incr: f64,
+    /// Exclamation marks beyond this count add no emphasis
+    pub max_emark: u32,
+    /// Above this many question marks, `max_qmark_incr` is used instead of `qmark_incr` per mark
+    pub max_qmark: u32,
+    /// Emphasis of all question marks together when there are more than `max_qmark`
+    pub max_qmark_incr: f64,
+    /// Approximate maximum of the unnormalized score. Lower values make the compound score
+    /// approach -1 and 1 faster, which suits short texts.
+    pub normalization_alpha: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            b_incr: B_INCR,
+            b_decr: B_DECR,
+            c_incr: C_INCR,
+            elongation_incr: E_INCR,
+            negation_scalar: NEGATION_SCALAR,
+            qmark_incr: QMARK_INCR,
+            emark_incr: EMARK_INCR,
+            max_emark: MAX_EMARK,
+            max_qmark: MAX_QMARK,
+            max_qmark_incr: MAX_QMARK_INCR,
+            normalization_alpha: NORMALIZATION_ALPHA,
+        }
+    }
+}
+
+/// How special case idioms and boosters are found in the words around a sentiment word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdiomMatching {
+    /// Idioms and boosters match whole words, so "so" does not match "also"
+    #[default]
+    Tokens,
+    /// Idioms and boosters match anywhere in the lowercase words joined by spaces, including
+    /// inside other words, and boosters before a word count twice, as in earlier versions
+    Substring,
+}
+
+/// How elongated words like "soooo goooood" are scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Elongation {
+    /// Elongated words that are not in the lexicon are scored as the word they elongate, i.e.
+    /// "goooood" as "good" and "soooo" as the booster "so"
+    #[default]
+    Normalize,
+    /// Like `Normalize`, and the valence of elongated sentiment words is emphasized by
+    /// `ScoringConfig::elongation_incr`
+    Emphasize,
+    /// Words are looked up as written, so elongated words are mostly neutral
+    Ignore,
+}
@@ -0,0 +1,213 @@
+use std::ops::Range;
+
+use unicase::UniCase;
+
+use crate::{
+    sentiment_intensity_analyzer::SentimentIntensity,
+    static_resources::{ABBREVIATIONS, TITLES},
+    tokenizer::Emoticons,
+};
+
+/// Return value of the `polarity_scores_document` method
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentSentiment {
+    pub sentences: Vec<SentenceSentiment>,
+    /// Mean of the scores of all sentences, or all zeros for a document without sentences
+    pub scores: SentimentIntensity,
+}
+
+/// Scores of a single sentence of a document
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SentenceSentiment {
+    /// Byte range of the sentence in the document
+    pub range: Range<usize>,
+    pub scores: SentimentIntensity,
+}
+
+/**
+ * Splits text into the byte ranges of its sentences, without surrounding whitespace.
+ *
+ * A sentence ends at a word ending in '.', '!' or '?' (optionally followed by closing quotes or
+ * brackets), or at a blank line. Titles ("Dr.") and dotted abbreviations ("e.g.") do not end a
+ * sentence, nor do list numbers. Other abbreviations ("etc.", "no."), initials and ellipses only
+ * continue the sentence when a lowercase word or a number follows. Emoji, emoticons of the lexicon
+ * and other symbols directly after the end of a sentence belong to that sentence.
+ **/
+pub(crate) fn split_sentences(text: &str, emoticons: &Emoticons) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut ended = false;
+    let mut prev_end = 0;
+
+    let mut chunks = text.split_whitespace().peekable();
+    while let Some(chunk) = chunks.next() {
+        let start = chunk.as_ptr() as usize - text.as_ptr() as usize;
+        let end = start + chunk.len();
+        let blank_line = text[prev_end..start].matches('\n').count() > 1;
+        prev_end = end;
+
+        match current.as_mut() {
+            Some(sentence) if !blank_line && !ended => {
+                sentence.end = end;
+                ended = ends_sentence(chunk, chunks.peek().copied(), false);
+            }
+            Some(sentence) if !blank_line && is_trailing_symbol(chunk, emoticons) => {
+                sentence.end = end;
+            }
+            _ => {
+                sentences.extend(current.take());
+                current = Some(start..end);
+                ended = ends_sentence(chunk, chunks.peek().copied(), true);
+            }
+        }
+    }
+    sentences.extend(current);
+    sentences
+}
+
+// Checks whether a sentence ends after this word
+fn ends_sentence(chunk: &str, next: Option<&str>, is_first: bool) -> bool {
+    let trimmed = chunk.trim_end_matches(['"', '\'', ')', ']', '}', '”', '’', '»']);
+    if !trimmed.ends_with(['.', '!', '?', '…']) {
+        return false;
+    }
+    if trimmed.ends_with("..") || trimmed.ends_with('…') {
+        // An ellipsis only ends a sentence if the next one starts with a capital letter
+        return !next.is_some_and(continues_sentence);
+    }
+    if trimmed.ends_with('.') {
+        let word = trimmed.trim_start_matches(|c: char| !c.is_alphanumeric());
+        if is_abbreviation(word, next) || (is_first && is_list_number(word)) {
+            return false;
+        }
+    }
+    true
+}
+
+// Checks whether a word ending in '.' is an abbreviation that does not end the sentence, given
+// the word after it
+fn is_abbreviation(word: &str, next: Option<&str>) -> bool {
+    let Some(stem) = word.strip_suffix('.') else {
+        return false;
+    };
+    let stem = UniCase::new(stem);
+    // Dotted abbreviations ("e.g.", "U.S.") and initials ("J.", as opposed to "plan B.")
+    let dotted = stem
+        .split('.')
+        .all(|part| part.chars().count() == 1 && part.chars().all(char::is_alphabetic));
+    if TITLES.contains(&stem) || (dotted && stem.contains('.')) {
+        return true;
+    }
+    (dotted || ABBREVIATIONS.contains(&stem)) && next.is_some_and(continues_sentence)
+}
+
+// Whether a word starts with a lowercase letter or a number, so the word before it does not end
+// a sentence
+fn continues_sentence(word: &str) -> bool {
+    word.chars()
+        .find(|c| c.is_alphanumeric())
+        .is_some_and(|c| !c.is_uppercase())
+}
+
+fn is_list_number(word: &str) -> bool {
+    word.strip_suffix('.')
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+// Emoji and emoticons after the end of a sentence, i.e. "Great! :D" or "Thanks! 😀"
+fn is_trailing_symbol(chunk: &str, emoticons: &Emoticons) -> bool {
+    !chunk.chars().any(char::is_alphanumeric) || emoticons.contains(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_resources::EMOTICONS;
+
+    fn sentences(text: &str) -> Vec<&str> {
+        split_sentences(text, &EMOTICONS)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            sentences("Great screen but bad battery. Shipping was fast!  Would I buy again?"),
+            vec![
+                "Great screen but bad battery.",
+                "Shipping was fast!",
+                "Would I buy again?"
+            ]
+        );
+        assert_eq!(
+            sentences("Dr. Smith was great, e.g. very kind. I was like... whatever. Wow... Ok"),
+            vec![
+                "Dr. Smith was great, e.g. very kind.",
+                "I was like... whatever.",
+                "Wow...",
+                "Ok"
+            ]
+        );
+        assert_eq!(
+            sentences("Loved it! 😀😀 :) :D See https://example.com/a.b?c=d. \"Really.\" Yes"),
+            vec![
+                "Loved it! 😀😀 :) :D",
+                "See https://example.com/a.b?c=d.",
+                "\"Really.\"",
+                "Yes"
+            ]
+        );
+        assert_eq!(
+            sentences("1. Fast shipping\n\n2. Terrible support"),
+            vec!["1. Fast shipping", "2. Terrible support"]
+        );
+        assert_eq!(
+            sentences("It was great. (I think it was terrible.)"),
+            vec!["It was great.", "(I think it was terrible.)"]
+        );
+        assert_eq!(
+            sentences("Wow. \"No way,\" he said."),
+            vec!["Wow.", "\"No way,\" he said."]
+        );
+        assert_eq!(
+            sentences("Done. #1 product ever"),
+            vec!["Done.", "#1 product ever"]
+        );
+        assert!(sentences(" \n ").is_empty());
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        assert_eq!(
+            sentences("I asked if it was any good and the answer was no. The screen is dim."),
+            vec![
+                "I asked if it was any good and the answer was no.",
+                "The screen is dim."
+            ]
+        );
+        assert_eq!(
+            sentences(
+                "We had to go with plan B. It was fine. Mr. Jones sold it at approx. ten bucks."
+            ),
+            vec![
+                "We had to go with plan B.",
+                "It was fine.",
+                "Mr. Jones sold it at approx. ten bucks."
+            ]
+        );
+        assert_eq!(
+            sentences(
+                "Bought pens, paper etc. Works well. Fig. 3 and no. 5 in the U.S. are great."
+            ),
+            vec![
+                "Bought pens, paper etc.",
+                "Works well.",
+                "Fig. 3 and no. 5 in the U.S. are great."
+            ]
+        );
+    }
+}
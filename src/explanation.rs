@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+use crate::sentiment_intensity_analyzer::SentimentIntensity;
+
+/// Return value of the `explain` method: the scores of a text and how every token contributed
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    pub tokens: Vec<TokenExplanation>,
+    /// Emphasis added to the total sentiment for '!' and '?'
+    pub punctuation_amplifier: f64,
+    /// Same as the result of `polarity_scores`
+    pub scores: SentimentIntensity,
+}
+
+/// How a single token contributed to the sentiment of a text
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenExplanation {
+    pub token: String,
+    /// Byte range of the token in the explained text. Tokens from the description of an emoji,
+    /// i.e. "grinning" and "face" for "😀", span the emoji.
+    pub span: Range<usize>,
+    /// Valence of the token in the lexicon, `None` if the token is not in it
+    pub lexicon_valence: Option<f64>,
+    /// Rules that changed the valence of the token, in the order they were applied
+    pub adjustments: Vec<Adjustment>,
+    /// Contribution of the token to the total sentiment, after all adjustments
+    pub valence: f64,
+}
+
+/// A change of a token's valence by a single rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adjustment {
+    pub rule: Rule,
+    pub before: f64,
+    pub after: f64,
+}
+
+/// The rules that adjust token valences. Distances count the tokens before the scored word,
+/// starting at 1 for the token right before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum Rule {
+    /// The token is a booster or dampener and carries no sentiment of its own
+    BoosterWord,
+    /// Emphasis of an all caps word in text that is not all caps
+    AllCaps,
+    /// Emphasis of an elongated word, i.e. "goooood"
+    Elongation,
+    /// A booster or dampener before the word, i.e. "very good"
+    Booster { distance: usize },
+    /// A negation before the word, or "never so" and "never this" emphasis
+    Negation { distance: usize },
+    /// A special case idiom around the word, i.e. "the bomb"
+    SpecialIdiom,
+    /// "least" before the word, i.e. "least compelling"
+    Least,
+    /// A sentiment laden idiom the word is part of, i.e. "under the weather"
+    Idiom,
+    /// Scaling of words around a contrastive marker like "but" or "although". `marker` is the
+    /// index of the first token of the marker.
+    Contrast { marker: usize },
+}
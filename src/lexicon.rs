@@ -0,0 +1,668 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, Read},
+    num::{ParseFloatError, ParseIntError},
+    path::Path,
+};
+
+use hashbrown::{Equivalent, HashMap};
+use unicase::UniCase;
+
+/// Maps words and emoticons to their sentiment valence. Lookups are case-insensitive.
+///
+/// Words can either be borrowed, e.g. from a lexicon file that outlives the analyzer, or owned,
+/// which makes it possible to build a `SentimentIntensityAnalyzer<'static>`.
+///
+/// Lexicon files use the tab separated VADER format: the word, its mean valence, the standard
+/// deviation and the list of individual ratings, e.g. `good\t1.9\t0.9434\t[2, 1, 1, 3, 2, 4, 2, 2, 1, 1]`.
+/// Only the mean is used for scoring, the other fields are validated.
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon<'a> {
+    words: HashMap<UniCase<Cow<'a, str>>, f64>,
+}
+
+impl<'a> Lexicon<'a> {
+    #[must_use]
+    pub fn new() -> Lexicon<'a> {
+        Lexicon {
+            words: HashMap::new(),
+        }
+    }
+
+    /// Parses a lexicon in the VADER format, borrowing the words from `text`
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Malformed` for the first line that is not in the VADER format
+    pub fn parse(text: &'a str) -> Result<Lexicon<'a>, LexiconError> {
+        let mut lexicon = Lexicon::new();
+        for (i, line) in text.lines().enumerate() {
+            if let Some((word, valence)) = parse_lexicon_line(line, i + 1)? {
+                lexicon.insert(word, valence);
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Reads a lexicon in the VADER format
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Io` if reading fails and `LexiconError::Malformed` for the first
+    /// line that is not in the VADER format
+    pub fn from_reader(reader: impl Read) -> Result<Lexicon<'static>, LexiconError> {
+        let mut lexicon = Lexicon::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if let Some((word, valence)) = parse_lexicon_line(&line, i + 1)? {
+                lexicon.insert(word.to_owned(), valence);
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Reads a lexicon in the VADER format from a file
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Io` if the file cannot be read and `LexiconError::Malformed` for
+    /// the first line that is not in the VADER format
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Lexicon<'static>, LexiconError> {
+        Lexicon::from_reader(File::open(path)?)
+    }
+
+    /// Inserts a word, returning its previous valence if it was already present
+    pub fn insert(&mut self, word: impl Into<Cow<'a, str>>, valence: f64) -> Option<f64> {
+        self.words.insert(UniCase::new(word.into()), valence)
+    }
+
+    /// Removes a word, returning its valence if it was present
+    pub fn remove(&mut self, word: &str) -> Option<f64> {
+        self.words.remove(&Key(UniCase::new(word)))
+    }
+
+    #[must_use]
+    pub fn get(&self, word: &str) -> Option<f64> {
+        self.words.get(&Key(UniCase::new(word))).copied()
+    }
+
+    #[must_use]
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains_key(&Key(UniCase::new(word)))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Iterates over all words and their valences, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.words
+            .iter()
+            .map(|(word, valence)| (word.as_ref(), *valence))
+    }
+
+    /// Copies all borrowed words, so the lexicon no longer borrows from its source
+    #[must_use]
+    pub fn into_owned(self) -> Lexicon<'static> {
+        self.words
+            .into_iter()
+            .map(|(word, valence)| (word.into_inner().into_owned(), valence))
+            .collect()
+    }
+
+    // Looks up a token without re-checking whether it is ascii
+    pub(crate) fn valence(&self, word: &UniCase<&str>) -> Option<f64> {
+        self.words.get(&Key(*word)).copied()
+    }
+}
+
+impl<'a, W: Into<Cow<'a, str>>> FromIterator<(W, f64)> for Lexicon<'a> {
+    fn from_iter<I: IntoIterator<Item = (W, f64)>>(iter: I) -> Self {
+        let mut lexicon = Lexicon::new();
+        lexicon.extend(iter);
+        lexicon
+    }
+}
+
+impl<'a, W: Into<Cow<'a, str>>> Extend<(W, f64)> for Lexicon<'a> {
+    fn extend<I: IntoIterator<Item = (W, f64)>>(&mut self, iter: I) {
+        for (word, valence) in iter {
+            self.insert(word, valence);
+        }
+    }
+}
+
+impl<'a> From<Lexicon<'a>> for Cow<'a, Lexicon<'a>> {
+    fn from(lexicon: Lexicon<'a>) -> Self {
+        Cow::Owned(lexicon)
+    }
+}
+
+impl<'a, 'b: 'a> From<&'a Lexicon<'b>> for Cow<'a, Lexicon<'a>> {
+    fn from(lexicon: &'a Lexicon<'b>) -> Self {
+        Cow::Borrowed(lexicon)
+    }
+}
+
+/// Domain-specific changes layered on top of a `Lexicon`.
+///
+/// An overlay can add words, change their valence or suppress them, so they are scored as if
+/// they were not in the lexicon at all. Lookups are case-insensitive.
+///
+/// ```
+/// use vader_sentimental::{LexiconOverlay, SentimentIntensityAnalyzer};
+///
+/// let mut oil_news = LexiconOverlay::new();
+/// oil_news.set("crude", 0.0).suppress("spill");
+/// let analyzer = SentimentIntensityAnalyzer::builder().overlay(oil_news).build();
+/// assert_eq!(analyzer.polarity_scores("Crude prices").compound, 0.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LexiconOverlay<'a> {
+    words: HashMap<UniCase<Cow<'a, str>>, OverlayEntry>,
+}
+
+/// How a `LexiconOverlay` changes a word
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayEntry {
+    /// The word is scored with this valence
+    Valence(f64),
+    /// The word is scored as if it were not in the lexicon
+    Suppressed,
+}
+
+impl<'a> LexiconOverlay<'a> {
+    #[must_use]
+    pub fn new() -> LexiconOverlay<'a> {
+        LexiconOverlay {
+            words: HashMap::new(),
+        }
+    }
+
+    /// Adds a word or changes its valence
+    pub fn set(&mut self, word: impl Into<Cow<'a, str>>, valence: f64) -> &mut Self {
+        self.words
+            .insert(UniCase::new(word.into()), OverlayEntry::Valence(valence));
+        self
+    }
+
+    /// Hides a word from the lexicons below this overlay
+    pub fn suppress(&mut self, word: impl Into<Cow<'a, str>>) -> &mut Self {
+        self.words
+            .insert(UniCase::new(word.into()), OverlayEntry::Suppressed);
+        self
+    }
+
+    /// Removes any change for a word, so lookups fall through to the lexicons below
+    pub fn reset(&mut self, word: &str) -> &mut Self {
+        self.words.remove(&Key(UniCase::new(word)));
+        self
+    }
+
+    /// Returns how the overlay changes a word, or `None` if it leaves the word alone
+    #[must_use]
+    pub fn get(&self, word: &str) -> Option<OverlayEntry> {
+        self.words.get(&Key(UniCase::new(word))).copied()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Copies all borrowed words, so the overlay no longer borrows from its source
+    #[must_use]
+    pub fn into_owned(self) -> LexiconOverlay<'static> {
+        LexiconOverlay {
+            words: self
+                .words
+                .into_iter()
+                .map(|(word, valence)| {
+                    (UniCase::new(word.into_inner().into_owned().into()), valence)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn entry(&self, word: &UniCase<&str>) -> Option<OverlayEntry> {
+        self.words.get(&Key(*word)).copied()
+    }
+
+    /// Iterates over the words the overlay gives a valence
+    pub(crate) fn words(&self) -> impl Iterator<Item = &str> {
+        self.words
+            .iter()
+            .filter(|(_, entry)| matches!(entry, OverlayEntry::Valence(_)))
+            .map(|(word, _)| word.as_ref())
+    }
+}
+
+impl<'a> From<LexiconOverlay<'a>> for Cow<'a, LexiconOverlay<'a>> {
+    fn from(overlay: LexiconOverlay<'a>) -> Self {
+        Cow::Owned(overlay)
+    }
+}
+
+impl<'a, 'b: 'a> From<&'a LexiconOverlay<'b>> for Cow<'a, LexiconOverlay<'a>> {
+    fn from(overlay: &'a LexiconOverlay<'b>) -> Self {
+        Cow::Borrowed(overlay)
+    }
+}
+
+/// Maps emoji to the textual description they are replaced with before scoring
+///
+/// Emoji lexicon files have two tab separated fields: the emoji and its description.
+#[derive(Debug, Clone, Default)]
+pub struct EmojiLexicon<'a> {
+    emoji: HashMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+impl<'a> EmojiLexicon<'a> {
+    #[must_use]
+    pub fn new() -> EmojiLexicon<'a> {
+        EmojiLexicon {
+            emoji: HashMap::new(),
+        }
+    }
+
+    /// Parses an emoji lexicon, borrowing the emoji and descriptions from `text`
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Malformed` for the first line without exactly two fields
+    pub fn parse(text: &'a str) -> Result<EmojiLexicon<'a>, LexiconError> {
+        let mut lexicon = EmojiLexicon::new();
+        for (i, line) in text.lines().enumerate() {
+            if let Some((emoji, description)) = parse_emoji_line(line, i + 1)? {
+                lexicon.insert(emoji, description);
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Reads an emoji lexicon
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Io` if reading fails and `LexiconError::Malformed` for the first
+    /// line without exactly two fields
+    pub fn from_reader(reader: impl Read) -> Result<EmojiLexicon<'static>, LexiconError> {
+        let mut lexicon = EmojiLexicon::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if let Some((emoji, description)) = parse_emoji_line(&line, i + 1)? {
+                lexicon.insert(emoji.to_owned(), description.to_owned());
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Reads an emoji lexicon from a file
+    ///
+    /// # Errors
+    ///
+    /// Returns `LexiconError::Io` if the file cannot be read and `LexiconError::Malformed` for
+    /// the first line without exactly two fields
+    pub fn from_path(path: impl AsRef<Path>) -> Result<EmojiLexicon<'static>, LexiconError> {
+        EmojiLexicon::from_reader(File::open(path)?)
+    }
+
+    /// Inserts an emoji, returning its previous description if it was already present
+    pub fn insert(
+        &mut self,
+        emoji: impl Into<Cow<'a, str>>,
+        description: impl Into<Cow<'a, str>>,
+    ) -> Option<Cow<'a, str>> {
+        self.emoji.insert(emoji.into(), description.into())
+    }
+
+    /// Removes an emoji, returning its description if it was present
+    pub fn remove(&mut self, emoji: &str) -> Option<Cow<'a, str>> {
+        self.emoji.remove(emoji)
+    }
+
+    #[must_use]
+    pub fn get(&self, emoji: &str) -> Option<&str> {
+        self.emoji.get(emoji).map(AsRef::as_ref)
+    }
+
+    #[must_use]
+    pub fn contains(&self, emoji: &str) -> bool {
+        self.emoji.contains_key(emoji)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.emoji.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.emoji.is_empty()
+    }
+
+    /// Iterates over all emoji and their descriptions, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.emoji
+            .iter()
+            .map(|(emoji, description)| (emoji.as_ref(), description.as_ref()))
+    }
+
+    /// Copies all borrowed emoji and descriptions, so the lexicon no longer borrows from its source
+    #[must_use]
+    pub fn into_owned(self) -> EmojiLexicon<'static> {
+        self.emoji
+            .into_iter()
+            .map(|(emoji, description)| (emoji.into_owned(), description.into_owned()))
+            .collect()
+    }
+}
+
+impl<'a, E: Into<Cow<'a, str>>, D: Into<Cow<'a, str>>> FromIterator<(E, D)> for EmojiLexicon<'a> {
+    fn from_iter<I: IntoIterator<Item = (E, D)>>(iter: I) -> Self {
+        let mut lexicon = EmojiLexicon::new();
+        lexicon.extend(iter);
+        lexicon
+    }
+}
+
+impl<'a, E: Into<Cow<'a, str>>, D: Into<Cow<'a, str>>> Extend<(E, D)> for EmojiLexicon<'a> {
+    fn extend<I: IntoIterator<Item = (E, D)>>(&mut self, iter: I) {
+        for (emoji, description) in iter {
+            self.insert(emoji, description);
+        }
+    }
+}
+
+impl<'a> From<EmojiLexicon<'a>> for Cow<'a, EmojiLexicon<'a>> {
+    fn from(lexicon: EmojiLexicon<'a>) -> Self {
+        Cow::Owned(lexicon)
+    }
+}
+
+impl<'a, 'b: 'a> From<&'a EmojiLexicon<'b>> for Cow<'a, EmojiLexicon<'a>> {
+    fn from(lexicon: &'a EmojiLexicon<'b>) -> Self {
+        Cow::Borrowed(lexicon)
+    }
+}
+
+/// Error returned when loading a lexicon fails
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LexiconError {
+    /// The lexicon could not be read
+    Io(io::Error),
+    /// A line of the lexicon is malformed. Lines and fields are 1-based.
+    Malformed {
+        line: usize,
+        field: usize,
+        reason: MalformedReason,
+    },
+}
+
+/// Why a lexicon line was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MalformedReason {
+    MissingField,
+    UnexpectedField,
+    EmptyField,
+    InvalidNumber(ParseFloatError),
+    InvalidRatings,
+    InvalidRating(ParseIntError),
+}
+
+impl fmt::Display for LexiconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexiconError::Io(err) => write!(f, "failed to read lexicon: {err}"),
+            LexiconError::Malformed {
+                line,
+                field,
+                reason,
+            } => write!(
+                f,
+                "malformed lexicon at line {line}, field {field}: {reason}"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MalformedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MalformedReason::MissingField => write!(f, "missing field"),
+            MalformedReason::UnexpectedField => write!(f, "unexpected field"),
+            MalformedReason::EmptyField => write!(f, "empty field"),
+            MalformedReason::InvalidNumber(err) => write!(f, "invalid number: {err}"),
+            MalformedReason::InvalidRatings => {
+                write!(f, "ratings must be a bracketed, comma separated list")
+            }
+            MalformedReason::InvalidRating(err) => write!(f, "invalid rating: {err}"),
+        }
+    }
+}
+
+impl Error for LexiconError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LexiconError::Io(err) => Some(err),
+            LexiconError::Malformed { reason, .. } => match reason {
+                MalformedReason::InvalidNumber(err) => Some(err),
+                MalformedReason::InvalidRating(err) => Some(err),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl From<io::Error> for LexiconError {
+    fn from(err: io::Error) -> Self {
+        LexiconError::Io(err)
+    }
+}
+
+// Splits a line into exactly `N` tab separated, non-empty fields. Empty lines are skipped.
+fn split_fields<const N: usize>(
+    line: &str,
+    line_no: usize,
+) -> Result<Option<[&str; N]>, LexiconError> {
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let malformed = |field, reason| LexiconError::Malformed {
+        line: line_no,
+        field,
+        reason,
+    };
+    let mut split_line = line.split('\t');
+    let mut fields = [""; N];
+    for (i, field) in fields.iter_mut().enumerate() {
+        *field = split_line
+            .next()
+            .ok_or_else(|| malformed(i + 1, MalformedReason::MissingField))?;
+        if field.trim().is_empty() {
+            return Err(malformed(i + 1, MalformedReason::EmptyField));
+        }
+    }
+    if split_line.next().is_some() {
+        return Err(malformed(N + 1, MalformedReason::UnexpectedField));
+    }
+    Ok(Some(fields))
+}
+
+fn parse_lexicon_line(line: &str, line_no: usize) -> Result<Option<(&str, f64)>, LexiconError> {
+    let Some([word, mean, std_dev, ratings]) = split_fields(line, line_no)? else {
+        return Ok(None);
+    };
+    let malformed = |field, reason| LexiconError::Malformed {
+        line: line_no,
+        field,
+        reason,
+    };
+    let valence = mean
+        .trim()
+        .parse()
+        .map_err(|err| malformed(2, MalformedReason::InvalidNumber(err)))?;
+    std_dev
+        .trim()
+        .parse::<f64>()
+        .map_err(|err| malformed(3, MalformedReason::InvalidNumber(err)))?;
+    let ratings = ratings
+        .trim()
+        .strip_prefix('[')
+        .and_then(|r| r.strip_suffix(']'))
+        .ok_or_else(|| malformed(4, MalformedReason::InvalidRatings))?;
+    for rating in ratings.split(',') {
+        rating
+            .trim()
+            .parse::<i32>()
+            .map_err(|err| malformed(4, MalformedReason::InvalidRating(err)))?;
+    }
+    Ok(Some((word, valence)))
+}
+
+fn parse_emoji_line(line: &str, line_no: usize) -> Result<Option<(&str, &str)>, LexiconError> {
+    Ok(split_fields(line, line_no)?.map(|[emoji, description]| (emoji, description)))
+}
+
+// Looks up owned or borrowed words by a borrowed word, without allocating
+#[derive(PartialEq, Eq)]
+pub(crate) struct Key<'w>(pub(crate) UniCase<&'w str>);
+
+impl Hash for Key<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Equivalent<UniCase<Cow<'_, str>>> for Key<'_> {
+    fn equivalent(&self, key: &UniCase<Cow<'_, str>>) -> bool {
+        self.0 == *key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_lexicon_lookup() {
+        let mut lexicon: Lexicon = [("good", 1.9), ("Bad", -2.5)].into_iter().collect();
+        assert_eq!(lexicon.get("GOOD"), Some(1.9));
+        assert_eq!(lexicon.valence(&UniCase::new("bad")), Some(-2.5));
+        assert!(!lexicon.contains("meh"));
+
+        assert_eq!(lexicon.insert(String::from("meh"), -0.3), None);
+        assert_eq!(lexicon.remove("good"), Some(1.9));
+        let owned: Lexicon<'static> = lexicon.into_owned();
+        assert_eq!(owned.len(), 2);
+        assert_eq!(owned.get("Meh"), Some(-0.3));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_lexicon_parsing() {
+        let lexicon =
+            Lexicon::parse("good\t1.9\t0.9434\t[2, 1, 1, 3, 2, 4, 2, 2, 1, 1]\n\n").unwrap();
+        assert_eq!(lexicon.get("good"), Some(1.9));
+
+        let owned =
+            Lexicon::from_reader(":)\t2.0\t1.18322\t[2, 2, 1, 1, 1, 1, 4, 3, 4, 1]".as_bytes())
+                .unwrap();
+        assert_eq!(owned.get(":)"), Some(2.0));
+
+        let malformed = |text| match Lexicon::parse(text) {
+            Err(LexiconError::Malformed {
+                line,
+                field,
+                reason,
+            }) => (line, field, reason),
+            other => panic!("expected malformed lexicon, got {other:?}"),
+        };
+        assert_eq!(
+            malformed("good\t1.9\t0.9\t[2]\nbad\t-2.5"),
+            (2, 3, MalformedReason::MissingField)
+        );
+        assert!(matches!(
+            malformed("good\tpositive\t0.9\t[2]"),
+            (1, 2, MalformedReason::InvalidNumber(_))
+        ));
+        assert_eq!(
+            malformed("good\t1.9\t0.9\t2, 1"),
+            (1, 4, MalformedReason::InvalidRatings)
+        );
+        assert!(matches!(
+            malformed("good\t1.9\t0.9\t[2, x]"),
+            (1, 4, MalformedReason::InvalidRating(_))
+        ));
+        assert_eq!(
+            malformed("good\t1.9\t0.9\t[2]\textra"),
+            (1, 5, MalformedReason::UnexpectedField)
+        );
+        assert_eq!(
+            malformed("\t1.9\t0.9\t[2]"),
+            (1, 1, MalformedReason::EmptyField)
+        );
+        assert_eq!(
+            Lexicon::parse("good\tpositive\t0.9\t[2]")
+                .unwrap_err()
+                .to_string(),
+            "malformed lexicon at line 1, field 2: invalid number: invalid float literal"
+        );
+
+        assert!(matches!(
+            Lexicon::from_path("does/not/exist.txt"),
+            Err(LexiconError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_emoji_lexicon_parsing() {
+        let lexicon = EmojiLexicon::parse("👽\talien\n").unwrap();
+        assert_eq!(lexicon.get("👽"), Some("alien"));
+        assert!(matches!(
+            EmojiLexicon::parse("👽\n"),
+            Err(LexiconError::Malformed {
+                line: 1,
+                field: 2,
+                reason: MalformedReason::MissingField
+            })
+        ));
+    }
+
+    #[test]
+    fn test_overlay_lookup() {
+        let mut overlay = LexiconOverlay::new();
+        overlay.set("Crude", 0.0).suppress("sick").set("gg", 1.5);
+        assert_eq!(overlay.get("crude"), Some(OverlayEntry::Valence(0.0)));
+        assert_eq!(overlay.get("SICK"), Some(OverlayEntry::Suppressed));
+        assert_eq!(overlay.get("good"), None);
+        overlay.reset("gg");
+        assert_eq!(overlay.into_owned().len(), 2);
+    }
+
+    #[test]
+    fn test_emoji_lexicon_lookup() {
+        let lexicon: EmojiLexicon = [("👽", "alien")].into_iter().collect();
+        assert_eq!(lexicon.get("👽"), Some("alien"));
+        assert_eq!(lexicon.get("👾"), None);
+    }
+}
@@ -2,10 +2,29 @@
 //!  * Hutto, C.J. & Gilbert, E.E. (2014). VADER: A Parsimonious Rule-based Model for
 //!  * Sentiment Analysis of Social Media Text. Eighth International Conference on
 //!  * Weblogs and Social Media (ICWSM-14). Ann Arbor, MI, June 2014.
+mod config;
+mod document;
+mod explanation;
+mod lexicon;
+mod negation;
+mod offsets;
 mod parsed_text;
+mod phrases;
+mod preprocess;
 mod sentiment_intensity_analyzer;
 mod static_resources;
+mod tokenizer;
 mod util;
 
+pub use crate::config::{Elongation, IdiomMatching, ScoringConfig};
+pub use crate::document::{DocumentSentiment, SentenceSentiment};
+pub use crate::explanation::{Adjustment, Explanation, Rule, TokenExplanation};
+pub use crate::lexicon::{
+    EmojiLexicon, Lexicon, LexiconError, LexiconOverlay, MalformedReason, OverlayEntry,
+};
+pub use crate::negation::{NegationDetector, Negations};
+pub use crate::preprocess::{Normalizer, Preprocessor, Redaction};
 pub use crate::sentiment_intensity_analyzer::SentimentIntensity;
 pub use crate::sentiment_intensity_analyzer::SentimentIntensityAnalyzer;
+pub use crate::sentiment_intensity_analyzer::SentimentIntensityAnalyzerBuilder;
+pub use crate::tokenizer::{DefaultTokenizer, Tokenizer};
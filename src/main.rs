@@ -5,6 +5,9 @@ use vader_sentimental::SentimentIntensityAnalyzer;
 #[command(version, about, long_about = None)]
 struct Args {
     text: String,
+    /// Show how every token contributed to the scores
+    #[arg(long)]
+    explain: bool,
 }
 
 /// Simple CLI tool to analyze the sentiment of a given text.
@@ -12,6 +15,11 @@ fn main() {
     let args = Args::parse();
 
     let analyzer = SentimentIntensityAnalyzer::new();
+    if args.explain {
+        let explanation = analyzer.explain(&args.text);
+        println!("{explanation:#?}");
+        return;
+    }
     let scores = analyzer.polarity_scores(&args.text);
 
     println!("Polarity scores");
@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+
+use unicase::UniCase;
+
+use crate::{phrases::PhraseTable, static_resources::NEGATIONS};
+
+/// Decides which tokens negate the sentiment words after them.
+///
+/// Implement this to support other dialects or languages, or use `Negations` to only change the
+/// list of negations.
+pub trait NegationDetector: Send + Sync {
+    /// Whether the token at `i` negates the words after it. Negations of several words, e.g.
+    /// "no way", end at their last word.
+    fn is_negation(&self, tokens: &[UniCase<&str>], i: usize) -> bool;
+}
+
+impl<T: NegationDetector + ?Sized> NegationDetector for &T {
+    fn is_negation(&self, tokens: &[UniCase<&str>], i: usize) -> bool {
+        (**self).is_negation(tokens, i)
+    }
+}
+
+/// A list of negations such as "not", "never" or "aint nobody", matched case-insensitively on
+/// whole tokens.
+///
+/// A word that is part of a longer negation only counts as a negation at the end of it, so "aint
+/// nobody" negates once. Optionally, every word containing "n't" is a negation.
+///
+/// ```
+/// use vader_sentimental::{Negations, SentimentIntensityAnalyzer};
+///
+/// let mut negations = Negations::builtin();
+/// negations.insert("no way").set_contractions(false);
+/// let analyzer = SentimentIntensityAnalyzer::builder()
+///     .negation_detector(negations)
+///     .build();
+/// assert!(analyzer.polarity_scores("no way good").compound < 0.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Negations<'a> {
+    negations: PhraseTable<'a, ()>,
+    contractions: bool,
+}
+
+impl<'a> Negations<'a> {
+    /// A list without negations, which does not treat words containing "n't" as negations
+    #[must_use]
+    pub fn new() -> Negations<'a> {
+        Negations::default()
+    }
+
+    /// The negations of VADER, which treats words containing "n't" as negations
+    #[must_use]
+    pub fn builtin() -> Negations<'static> {
+        NEGATIONS.clone()
+    }
+
+    /// Adds a negation of one or more words
+    pub fn insert(&mut self, negation: impl Into<Cow<'a, str>>) -> &mut Self {
+        self.negations.insert(negation, ());
+        self
+    }
+
+    /// Removes a negation, including built-in ones
+    pub fn remove(&mut self, negation: &str) -> &mut Self {
+        self.negations.remove(negation);
+        self
+    }
+
+    /// Whether every word containing "n't", like "didn't" or "wouldn't", is a negation
+    pub fn set_contractions(&mut self, contractions: bool) -> &mut Self {
+        self.contractions = contractions;
+        self
+    }
+
+    /// Copies all borrowed words, so the list no longer borrows from its source
+    #[must_use]
+    pub fn into_owned(self) -> Negations<'static> {
+        Negations {
+            negations: self.negations.into_owned(),
+            contractions: self.contractions,
+        }
+    }
+}
+
+impl NegationDetector for Negations<'_> {
+    fn is_negation(&self, tokens: &[UniCase<&str>], i: usize) -> bool {
+        // A word that is part of a longer negation is only a negation at the end of it
+        let negation = self
+            .negations
+            .match_around(tokens, i)
+            .map(|(range, ())| range.end - 1 == i);
+        negation.unwrap_or(self.contractions && tokens[i].contains("n't"))
+    }
+}
+
+impl<'a> FromIterator<&'a str> for Negations<'a> {
+    /// Collects a list of negations, which does not treat words containing "n't" as negations
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        Negations {
+            negations: iter.into_iter().map(|negation| (negation, ())).collect(),
+            contractions: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negations(negations: &Negations, text: &str) -> Vec<bool> {
+        let tokens: Vec<UniCase<&str>> = text.split_whitespace().map(UniCase::new).collect();
+        (0..tokens.len())
+            .map(|i| negations.is_negation(&tokens, i))
+            .collect()
+    }
+
+    #[test]
+    fn test_negations() {
+        let mut slang: Negations = ["aint", "aint nobody", "no way", "nah"]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            negations(&slang, "Aint nobody got time, no way"),
+            vec![false, true, false, false, false, true]
+        );
+        assert_eq!(negations(&slang, "aint it good"), vec![true, false, false]);
+        assert_eq!(negations(&slang, "couldn't care"), vec![false, false]);
+        slang.set_contractions(true).remove("nah");
+        assert_eq!(
+            negations(&slang, "couldn't care nah"),
+            vec![true, false, false]
+        );
+
+        let builtin = Negations::builtin();
+        assert_eq!(
+            negations(&builtin, "not wasn't ok"),
+            vec![true, true, false]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::ops::Range;
+
+/// Maps byte offsets in a rewritten text, i.e. with emoji replaced by their description, back to
+/// the text it was rewritten from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OffsetMap {
+    // Rewritten parts in order: their range in the rewritten text and the range they replace in
+    // the original text. Text between them is unchanged.
+    edits: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    pub(crate) fn clear(&mut self) {
+        self.edits.clear();
+    }
+
+    /// Records that `rewritten` replaces `original`. Edits must be recorded in order.
+    pub(crate) fn push(&mut self, rewritten: Range<usize>, original: Range<usize>) {
+        self.edits.push((rewritten, original));
+    }
+
+    /// Maps a range of the rewritten text to the original text. A range that starts or ends in a
+    /// rewritten part is widened to the whole part it replaces.
+    pub(crate) fn map(&self, span: &Range<usize>) -> Range<usize> {
+        let start = match self.edit_before(|start| start <= span.start) {
+            Some((rewritten, original)) if span.start < rewritten.end => original.start,
+            Some((rewritten, original)) => span.start - rewritten.end + original.end,
+            None => span.start,
+        };
+        let end = match self.edit_before(|start| start < span.end) {
+            Some((rewritten, original)) if span.end <= rewritten.end => original.end,
+            Some((rewritten, original)) => span.end - rewritten.end + original.end,
+            None => span.end,
+        };
+        start..end.max(start)
+    }
+
+    // Finds the last edit whose rewritten range starts before an offset
+    fn edit_before(&self, before: impl Fn(usize) -> bool) -> Option<&(Range<usize>, Range<usize>)> {
+        let count = self
+            .edits
+            .partition_point(|(rewritten, _)| before(rewritten.start));
+        count.checked_sub(1).map(|i| &self.edits[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_map() {
+        // "hi 😀!" rewritten to "hi grinning face!"
+        let mut offsets = OffsetMap::default();
+        offsets.push(3..16, 3..7);
+        assert_eq!(offsets.map(&(0..2)), 0..2);
+        assert_eq!(offsets.map(&(3..11)), 3..7);
+        assert_eq!(offsets.map(&(12..16)), 3..7);
+        assert_eq!(offsets.map(&(12..17)), 3..8);
+        assert_eq!(offsets.map(&(16..17)), 7..8);
+
+        // "a😀b" rewritten to "a grinning face b", with inserted spaces
+        let mut offsets = OffsetMap::default();
+        offsets.push(1..2, 1..1);
+        offsets.push(2..15, 1..5);
+        offsets.push(15..16, 5..5);
+        assert_eq!(offsets.map(&(2..10)), 1..5);
+        assert_eq!(offsets.map(&(16..17)), 5..6);
+        assert_eq!(offsets.map(&(0..1)), 0..1);
+
+        offsets.clear();
+        assert_eq!(offsets.map(&(2..10)), 2..10);
+    }
+}
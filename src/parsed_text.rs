@@ -1,56 +1,65 @@
-use std::cmp::min;
+use std::{cmp::min, ops::Range};
 
 use unicase::UniCase;
 
-use crate::static_resources::{
-    EMARK_INCR, MAX_EMARK, MAX_QMARK, MAX_QMARK_INCR, PUNCTUATION, QMARK_INCR,
-};
+use crate::config::ScoringConfig;
+use crate::tokenizer::Tokenizer;
 use crate::util::is_all_caps;
 
 /**
  *  Stores tokens and useful info about text
  **/
 pub struct ParsedText<'a> {
+    pub text: &'a str,
     pub tokens: Vec<UniCase<&'a str>>,
+    // Byte ranges of the tokens in the text
+    pub spans: Vec<Range<usize>>,
     pub has_mixed_caps: bool,
     pub punc_amplifier: f64,
 }
 
 impl ParsedText<'_> {
     //Tokenizes and extracts useful properties of input text
-    pub fn from_text(text: &str) -> ParsedText<'_> {
-        let tokens = ParsedText::tokenize(text);
+    pub fn from_text<'a>(
+        text: &'a str,
+        tokenizer: &dyn Tokenizer,
+        config: &ScoringConfig,
+    ) -> ParsedText<'a> {
+        let spans = ParsedText::tokenize(text, tokenizer);
+        let tokens: Vec<UniCase<&str>> = spans
+            .iter()
+            .map(|span| UniCase::new(&text[span.clone()]))
+            .collect();
         let has_mixed_caps = ParsedText::has_mixed_caps(&tokens);
-        let punc_amplifier = ParsedText::get_punctuation_emphasis(text);
+        let punc_amplifier = ParsedText::get_punctuation_emphasis(text, config);
         ParsedText {
+            text,
             tokens,
+            spans,
             has_mixed_caps,
             punc_amplifier,
         }
     }
 
-    pub fn tokenize(text: &str) -> Vec<UniCase<&str>> {
-        let tokens = text
-            .split_whitespace()
-            .filter_map(|s| {
-                if s.len() <= 1 {
-                    None
-                } else {
-                    Some(UniCase::new(ParsedText::strip_punc_if_word(s)))
-                }
-            })
-            .collect();
-        tokens
+    // Byte range of the token at `i` in the text
+    pub fn span(&self, i: usize) -> Range<usize> {
+        self.spans[i].clone()
     }
 
-    // Removes punctuation from words, ie "hello!!!" -> "hello" and ",don't??" -> "don't"
-    // Keeps most emoticons, ie ":^)" -> ":^)"\
-    fn strip_punc_if_word(token: &str) -> &str {
-        let stripped = token.trim_matches(|c| PUNCTUATION.contains(c));
-        if stripped.len() <= 1 {
-            return token;
-        }
-        stripped
+    // Tokenizes the text, and drops ranges of a single byte, which phrases never contain, and
+    // ranges that are not in the text, not on character boundaries or start before the previous
+    // token
+    pub fn tokenize(text: &str, tokenizer: &dyn Tokenizer) -> Vec<Range<usize>> {
+        let mut spans = tokenizer.tokenize(text);
+        let mut last = 0;
+        spans.retain(|span| {
+            let valid = last <= span.start && span.len() > 1 && text.get(span.clone()).is_some();
+            if valid {
+                last = span.start;
+            }
+            valid
+        });
+        spans
     }
 
     // Determines if message has a mix of both all caps and non all caps words
@@ -72,18 +81,19 @@ impl ParsedText<'_> {
     //uses empirical values to determine how the use of '?' and '!' contribute to sentiment
     // TODO: Floating points here is a concern, use `rust_decimal` instead.
     // TODO: Naive way of counting bytes, use `bytecount` crate
-    // TODO: usize to i32 _can_ overflow
-    #[allow(clippy::cast_possible_truncation)]
-    #[allow(clippy::cast_possible_wrap)]
     #[allow(clippy::naive_bytecount)]
-    fn get_punctuation_emphasis(text: &str) -> f64 {
-        let emark_count = text.as_bytes().iter().filter(|b| **b == b'!').count() as i32;
-        let qmark_count = text.as_bytes().iter().filter(|b| **b == b'?').count() as i32;
+    fn get_punctuation_emphasis(text: &str, config: &ScoringConfig) -> f64 {
+        let count = |mark| {
+            let count = text.as_bytes().iter().filter(|b| **b == mark).count();
+            u32::try_from(count).unwrap_or(u32::MAX)
+        };
+        let emark_count = count(b'!');
+        let qmark_count = count(b'?');
 
-        let emark_emph = f64::from(min(emark_count, MAX_EMARK)) * EMARK_INCR;
-        let mut qmark_emph = f64::from(qmark_count) * QMARK_INCR;
-        if qmark_count > MAX_QMARK {
-            qmark_emph = MAX_QMARK_INCR;
+        let emark_emph = f64::from(min(emark_count, config.max_emark)) * config.emark_incr;
+        let mut qmark_emph = f64::from(qmark_count) * config.qmark_incr;
+        if qmark_count > config.max_qmark {
+            qmark_emph = config.max_qmark_incr;
         }
         qmark_emph + emark_emph
     }
@@ -92,12 +102,17 @@ impl ParsedText<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenizer::DefaultTokenizer;
 
     #[test]
     #[allow(clippy::float_cmp)]
     fn test_parsed_text() {
         let messy_text = "WOAH!!! ,Who? DO u Think you're?? :) :D :^(";
-        let parsed_messy = ParsedText::from_text(messy_text);
+        let parsed_messy = ParsedText::from_text(
+            messy_text,
+            &DefaultTokenizer::new(),
+            &ScoringConfig::default(),
+        );
         let expected_text: Vec<UniCase<&str>> =
             ["WOAH", "Who", "DO", "Think", "you\'re", ":)", ":D", ":^("]
                 .iter()
@@ -107,17 +122,13 @@ mod tests {
         assert!(parsed_messy.has_mixed_caps);
         assert_eq!(parsed_messy.punc_amplifier, 1.416);
 
-        assert!(!ParsedText::has_mixed_caps(&ParsedText::tokenize(
-            "yeah!!! I'm aLLERGIC to ShouTING."
-        )));
-        assert!(!ParsedText::has_mixed_caps(&ParsedText::tokenize(
-            "OH MAN I LOVE SHOUTING!"
-        )));
-        assert!(ParsedText::has_mixed_caps(&ParsedText::tokenize(
-            "I guess I CAN'T MAKE UP MY MIND"
-        )));
-        assert!(ParsedText::has_mixed_caps(&ParsedText::tokenize(
-            "Hmm, yeah ME NEITHER"
-        )));
+        let has_mixed_caps = |text| {
+            ParsedText::from_text(text, &DefaultTokenizer::new(), &ScoringConfig::default())
+                .has_mixed_caps
+        };
+        assert!(!has_mixed_caps("yeah!!! I'm aLLERGIC to ShouTING."));
+        assert!(!has_mixed_caps("OH MAN I LOVE SHOUTING!"));
+        assert!(has_mixed_caps("I guess I CAN'T MAKE UP MY MIND"));
+        assert!(has_mixed_caps("Hmm, yeah ME NEITHER"));
     }
 }
@@ -0,0 +1,259 @@
+use std::{borrow::Cow, ops::Range};
+
+use hashbrown::{HashMap, HashSet};
+use unicase::UniCase;
+
+use crate::lexicon::Key;
+
+/// Multi-word phrases with a valence, matched on whole tokens.
+///
+/// When several phrases match, the phrase with the most words wins, and phrases of the same length
+/// are ordered alphabetically, so results do not depend on hash order.
+#[derive(Debug, Clone)]
+pub(crate) struct PhraseTable<'a, V = f64> {
+    // Longest phrase first, then in alphabetical order
+    phrases: Vec<Phrase<'a, V>>,
+    // Indices of the phrases by their first word, in the same order
+    by_first_word: HashMap<UniCase<Cow<'a, str>>, Vec<usize>>,
+    // Every word of every phrase, to skip texts that cannot contain any of them
+    words: HashSet<UniCase<Cow<'a, str>>>,
+    max_words: usize,
+}
+
+// Not derived, which would require `V: Default`
+impl<V> Default for PhraseTable<'_, V> {
+    fn default() -> Self {
+        PhraseTable {
+            phrases: Vec::new(),
+            by_first_word: HashMap::new(),
+            words: HashSet::new(),
+            max_words: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Phrase<'a, V> {
+    // Lowercase words joined by single spaces
+    text: Cow<'a, str>,
+    words: Vec<UniCase<Cow<'a, str>>>,
+    valence: V,
+}
+
+impl<'a, V: Copy> PhraseTable<'a, V> {
+    /// Adds a phrase, or replaces the valence of a phrase that is already in the table.
+    /// Words are separated by whitespace; a phrase without words is ignored.
+    pub(crate) fn insert(&mut self, phrase: impl Into<Cow<'a, str>>, valence: V) {
+        if self.insert_unindexed(phrase.into(), valence) {
+            self.reindex();
+        }
+    }
+
+    // Adds or replaces a phrase, and returns whether the index needs to be rebuilt
+    fn insert_unindexed(&mut self, phrase: Cow<'a, str>, valence: V) -> bool {
+        let text = words(&phrase).collect::<Vec<_>>().join(" ").to_lowercase();
+        let text = if text == *phrase {
+            phrase.clone()
+        } else {
+            Cow::Owned(text)
+        };
+        let words = split_words(phrase);
+        if words.is_empty() {
+            return false;
+        }
+        if let Some(entry) = self.phrases.iter_mut().find(|p| p.words == words) {
+            entry.valence = valence;
+            return false;
+        }
+        self.phrases.push(Phrase {
+            text,
+            words,
+            valence,
+        });
+        true
+    }
+
+    /// Removes a phrase, and returns its valence if it was in the table
+    pub(crate) fn remove(&mut self, phrase: &str) -> Option<V> {
+        let words: Vec<UniCase<&str>> = words(phrase).map(UniCase::new).collect();
+        let at = self
+            .phrases
+            .iter()
+            .position(|p| p.words.iter().eq(&words))?;
+        let removed = self.phrases.remove(at);
+        self.reindex();
+        Some(removed.valence)
+    }
+
+    // Sorts the phrases and rebuilds the lookups derived from them
+    fn reindex(&mut self) {
+        self.phrases.sort_by(|a, b| {
+            b.words
+                .len()
+                .cmp(&a.words.len())
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        self.by_first_word.clear();
+        for (index, phrase) in self.phrases.iter().enumerate() {
+            self.by_first_word
+                .entry(phrase.words[0].clone())
+                .or_default()
+                .push(index);
+        }
+        self.words = self
+            .phrases
+            .iter()
+            .flat_map(|p| p.words.iter().cloned())
+            .collect();
+        self.max_words = self.phrases.first().map_or(0, |p| p.words.len());
+    }
+
+    /// Copies all borrowed words, so the table no longer borrows from its source
+    pub(crate) fn into_owned(self) -> PhraseTable<'static, V> {
+        let owned =
+            |word: UniCase<Cow<'a, str>>| UniCase::new(Cow::Owned(word.into_inner().into_owned()));
+        PhraseTable {
+            phrases: self
+                .phrases
+                .into_iter()
+                .map(|p| Phrase {
+                    text: Cow::Owned(p.text.into_owned()),
+                    words: p.words.into_iter().map(owned).collect(),
+                    valence: p.valence,
+                })
+                .collect(),
+            by_first_word: self
+                .by_first_word
+                .into_iter()
+                .map(|(first, indices)| (owned(first), indices))
+                .collect(),
+            words: self.words.into_iter().map(owned).collect(),
+            max_words: self.max_words,
+        }
+    }
+
+    /// Whether any phrase contains the word
+    pub(crate) fn contains_word(&self, word: &UniCase<&str>) -> bool {
+        self.words.contains(&Key(*word))
+    }
+
+    /// Iterates over the phrases as lowercase words joined by single spaces, with their valence.
+    /// Longer phrases come first, and phrases of the same length are in alphabetical order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, V)> {
+        self.phrases.iter().map(|p| (p.text.as_ref(), p.valence))
+    }
+
+    /// Finds the longest phrase at the start of the tokens, and returns its length in tokens and
+    /// its valence
+    pub(crate) fn longest_match(&self, tokens: &[UniCase<&str>]) -> Option<(usize, V)> {
+        let first = tokens.first()?;
+        self.by_first_word
+            .get(&Key(*first))?
+            .iter()
+            .map(|&index| &self.phrases[index])
+            .find(|p| p.words.len() <= tokens.len() && p.words.iter().eq(&tokens[..p.words.len()]))
+            .map(|p| (p.words.len(), p.valence))
+    }
+
+    /// Finds the first phrase, in the order of `iter`, that occurs anywhere in the tokens
+    pub(crate) fn first_within(&self, tokens: &[UniCase<&str>]) -> Option<V> {
+        self.phrases
+            .iter()
+            .find(|p| tokens.windows(p.words.len()).any(|w| p.words.iter().eq(w)))
+            .map(|p| p.valence)
+    }
+
+    /// Finds the phrase that contains the token at `i`, and returns the range of its tokens and
+    /// its valence. If several phrases contain the token, the one that starts first wins.
+    pub(crate) fn match_around(
+        &self,
+        tokens: &[UniCase<&str>],
+        i: usize,
+    ) -> Option<(Range<usize>, V)> {
+        let first = (i + 1).saturating_sub(self.max_words);
+        (first..=i).find_map(|start| {
+            let (len, valence) = self.longest_match(&tokens[start..])?;
+            (start + len > i).then_some((start..start + len, valence))
+        })
+    }
+}
+
+impl<'a, P: Into<Cow<'a, str>>, V: Copy> FromIterator<(P, V)> for PhraseTable<'a, V> {
+    fn from_iter<I: IntoIterator<Item = (P, V)>>(iter: I) -> Self {
+        let mut table = PhraseTable::default();
+        for (phrase, valence) in iter {
+            table.insert_unindexed(phrase.into(), valence);
+        }
+        table.reindex();
+        table
+    }
+}
+
+fn split_words(phrase: Cow<'_, str>) -> Vec<UniCase<Cow<'_, str>>> {
+    match phrase {
+        Cow::Borrowed(phrase) => words(phrase)
+            .map(|w| UniCase::new(Cow::Borrowed(w)))
+            .collect(),
+        Cow::Owned(phrase) => words(&phrase)
+            .map(|w| UniCase::new(Cow::Owned(w.to_owned())))
+            .collect(),
+    }
+}
+
+// Splits a phrase like the tokenizer splits text, which drops single character words, so
+// "break a leg" matches the tokens "break" and "leg"
+fn words(phrase: &str) -> impl Iterator<Item = &str> {
+    phrase.split_whitespace().filter(|w| w.len() > 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_match() {
+        let mut table: PhraseTable = [("in the red", -2.0), ("in the", 0.5)]
+            .into_iter()
+            .collect();
+        table.insert(String::from("In the black"), 2.0);
+        table.insert("in the  RED", -1.0);
+
+        let tokens: Vec<UniCase<&str>> = ["in", "the", "Red", "zone"]
+            .into_iter()
+            .map(UniCase::new)
+            .collect();
+        assert_eq!(table.longest_match(&tokens), Some((3, -1.0)));
+        assert_eq!(table.longest_match(&tokens[..2]), Some((2, 0.5)));
+        assert_eq!(table.longest_match(&tokens[1..]), None);
+        assert_eq!(table.longest_match(&[]), None);
+
+        assert_eq!(table.match_around(&tokens, 1), Some((0..3, -1.0)));
+        assert_eq!(table.match_around(&tokens, 3), None);
+        assert_eq!(table.first_within(&tokens), Some(-1.0));
+        assert_eq!(table.first_within(&tokens[1..]), None);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut table: PhraseTable = [("yeah right", -2.0)].into_iter().collect();
+        table.insert(String::from("Not  BAD at all"), 2.0);
+        table.insert("could be worse", 1.5);
+        table.insert("bad ass", 1.5);
+        let phrases: Vec<(&str, f64)> = table.iter().collect();
+        assert_eq!(
+            phrases,
+            vec![
+                ("not bad at all", 2.0),
+                ("could be worse", 1.5),
+                ("bad ass", 1.5),
+                ("yeah right", -2.0)
+            ]
+        );
+        assert!(table.contains_word(&UniCase::new("ALL")));
+
+        assert_eq!(table.remove("not bad AT all"), Some(2.0));
+        assert_eq!(table.remove("not bad"), None);
+        assert!(!table.contains_word(&UniCase::new("all")));
+        assert!(table.contains_word(&UniCase::new("right")));
+    }
+}
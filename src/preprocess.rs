@@ -0,0 +1,324 @@
+use std::{borrow::Cow, ops::Range};
+
+use crate::{
+    offsets::OffsetMap,
+    static_resources::{
+        EMAIL_RE, HTML_ENTITY_RE, HTML_TAG_RE, MARKDOWN_BLOCK_RE, MARKDOWN_CODE_RE,
+        MARKDOWN_EMPHASIS_RES, MARKDOWN_LINK_RE, MENTION_RE, URL_RE,
+    },
+};
+
+/// A step that rewrites text before it is tokenized, e.g. to remove entities that should not be
+/// scored.
+///
+/// Steps only report what to replace, so the positions of tokens can still be mapped back to the
+/// original text.
+///
+/// ```
+/// use std::{borrow::Cow, ops::Range};
+///
+/// use vader_sentimental::{Preprocessor, SentimentIntensityAnalyzer};
+///
+/// // Removes ticket numbers like "#1234"
+/// struct Tickets;
+///
+/// impl Preprocessor for Tickets {
+///     fn replacements<'t>(&self, text: &'t str) -> Vec<(Range<usize>, Cow<'t, str>)> {
+///         text.match_indices('#')
+///             .map(|(start, _)| {
+///                 let digits = text[start + 1..].bytes().take_while(u8::is_ascii_digit).count();
+///                 (start..start + 1 + digits, Cow::Borrowed(""))
+///             })
+///             .collect()
+///     }
+/// }
+///
+/// let analyzer = SentimentIntensityAnalyzer::builder().preprocessor(Tickets).build();
+/// let explanation = analyzer.explain("#1234 works great");
+/// assert_eq!(explanation.tokens[0].token, "works");
+/// assert_eq!(explanation.tokens[0].span, 6..11);
+/// ```
+pub trait Preprocessor: Send + Sync {
+    /// Finds the parts of the text to replace, as byte ranges of the text with their
+    /// replacement. Ranges have to be in order; a range that overlaps an earlier one or is not on
+    /// character boundaries is ignored.
+    fn replacements<'t>(&self, text: &'t str) -> Vec<(Range<usize>, Cow<'t, str>)>;
+}
+
+impl<T: Preprocessor + ?Sized> Preprocessor for &T {
+    fn replacements<'t>(&self, text: &'t str) -> Vec<(Range<usize>, Cow<'t, str>)> {
+        (**self).replacements(text)
+    }
+}
+
+/// Built-in preprocessing steps for social media and support messages.
+///
+/// Steps run in the order they are added to the analyzer. Add `Html` and `Markdown` before the
+/// others, so links and markup are handled as a whole.
+///
+/// ```
+/// use vader_sentimental::{Normalizer, Redaction, SentimentIntensityAnalyzer};
+///
+/// let analyzer = SentimentIntensityAnalyzer::builder()
+///     .preprocessor(Normalizer::Html)
+///     .preprocessor(Normalizer::Urls(Redaction::Mask))
+///     .build();
+/// let explanation = analyzer.explain("<b>See</b> https://example.com/i-love-it");
+/// let tokens: Vec<&str> = explanation.tokens.iter().map(|t| &*t.token).collect();
+/// assert_eq!(tokens, vec!["See", "url"]);
+/// assert_eq!(explanation.scores.compound, 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Normalizer {
+    /// URLs like `https://example.com/i-love-it` and `www.example.com`, masked as `<url>`
+    Urls(Redaction),
+    /// Mentions like `@support`, masked as `<mention>`
+    Mentions(Redaction),
+    /// Email addresses like `jane@example.com`, masked as `<email>`
+    Emails(Redaction),
+    /// Removes HTML tags, comments, scripts and styles, and decodes character references like
+    /// `&amp;`
+    Html,
+    /// Removes Markdown code, emphasis, heading, quote and rule markers, and the targets of links
+    /// and images, keeping their text
+    Markdown,
+}
+
+/// How a `Normalizer` removes the entities it finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Redaction {
+    /// Replaces the entity with a space
+    #[default]
+    Strip,
+    /// Replaces the entity with a neutral placeholder like `<url>`, so it still counts as a token
+    Mask,
+}
+
+impl Redaction {
+    fn replacement(self, placeholder: &'static str) -> Cow<'static, str> {
+        match self {
+            Redaction::Strip => Cow::Borrowed(" "),
+            Redaction::Mask => Cow::Borrowed(placeholder),
+        }
+    }
+}
+
+impl Preprocessor for Normalizer {
+    fn replacements<'t>(&self, text: &'t str) -> Vec<(Range<usize>, Cow<'t, str>)> {
+        match *self {
+            Normalizer::Urls(redaction) => URL_RE
+                .find_iter(text)
+                .map(|url| {
+                    (
+                        url.start()..url.start() + url_len(url.as_str()),
+                        redaction.replacement("<url>"),
+                    )
+                })
+                .collect(),
+            Normalizer::Mentions(redaction) => MENTION_RE
+                .captures_iter(text)
+                .filter_map(|captures| captures.get(1))
+                .map(|mention| (mention.range(), redaction.replacement("<mention>")))
+                .collect(),
+            Normalizer::Emails(redaction) => EMAIL_RE
+                .find_iter(text)
+                .map(|email| (email.range(), redaction.replacement("<email>")))
+                .collect(),
+            Normalizer::Html => html(text),
+            Normalizer::Markdown => markdown(text),
+        }
+    }
+}
+
+const URL_TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"', ')', ']'];
+
+// Length of a URL without the punctuation after it, which most likely belongs to the sentence. A
+// closing parenthesis is kept if it closes one in the URL, like in Wikipedia links.
+fn url_len(url: &str) -> usize {
+    let mut url = url;
+    while let Some(c) = url.chars().next_back().filter(|c| URL_TRAILING.contains(c)) {
+        if c == ')' && url.matches('(').count() >= url.matches(')').count() {
+            break;
+        }
+        url = &url[..url.len() - c.len_utf8()];
+    }
+    url.len()
+}
+
+fn html(text: &str) -> Vec<(Range<usize>, Cow<'static, str>)> {
+    let tags = HTML_TAG_RE
+        .find_iter(text)
+        .map(|tag| (tag.range(), Cow::Borrowed(" ")));
+    let entities = HTML_ENTITY_RE.captures_iter(text).filter_map(|captures| {
+        let decoded = if let Some(decimal) = captures.get(1) {
+            char::from_u32(decimal.as_str().parse().ok()?)?
+        } else if let Some(hex) = captures.get(2) {
+            char::from_u32(u32::from_str_radix(hex.as_str(), 16).ok()?)?
+        } else {
+            match captures.get(3)?.as_str() {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                _ => ' ',
+            }
+        };
+        Some((captures.get(0)?.range(), Cow::Owned(decoded.to_string())))
+    });
+    in_order(tags.chain(entities).collect())
+}
+
+fn markdown(text: &str) -> Vec<(Range<usize>, Cow<'static, str>)> {
+    let mut replacements: Vec<(Range<usize>, Cow<'static, str>)> = Vec::new();
+    for code in MARKDOWN_CODE_RE.find_iter(text) {
+        replacements.push((code.range(), Cow::Borrowed(" ")));
+    }
+    let mut remove = |range: Range<usize>| replacements.push((range, Cow::Borrowed("")));
+    for block in MARKDOWN_BLOCK_RE.find_iter(text) {
+        remove(block.range());
+    }
+    for link in MARKDOWN_LINK_RE.captures_iter(text) {
+        if let (Some(open), Some(close)) = (link.get(1), link.get(2)) {
+            remove(open.range());
+            remove(close.range());
+        }
+    }
+    for (marker, emphasis) in MARKDOWN_EMPHASIS_RES.iter() {
+        for captures in emphasis.captures_iter(text) {
+            let (Some(open), Some(close)) = (captures.get(1), captures.get(2)) else {
+                continue;
+            };
+            // Markers are not part of a word, like in "snake_case"
+            let before = text[..open.start()].chars().next_back();
+            let after = text[close.end()..].chars().next();
+            let outside = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric() && c != *marker);
+            if outside(before) && outside(after) {
+                remove(open.range());
+                remove(close.range());
+            }
+        }
+    }
+    in_order(replacements)
+}
+
+// Sorts replacements found by several patterns, and drops those inside earlier ones
+fn in_order(
+    mut replacements: Vec<(Range<usize>, Cow<'static, str>)>,
+) -> Vec<(Range<usize>, Cow<'static, str>)> {
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut end = 0;
+    replacements.retain(|(range, _)| {
+        let keep = range.start >= end;
+        if keep {
+            end = range.end;
+        }
+        keep
+    });
+    replacements
+}
+
+// Runs a step, writing the rewritten text to `result` and how it maps back to `offsets`
+pub(crate) fn preprocess(
+    preprocessor: &dyn Preprocessor,
+    text: &str,
+    result: &mut String,
+    offsets: &mut OffsetMap,
+) {
+    result.clear();
+    offsets.clear();
+    let mut last = 0;
+    for (range, replacement) in preprocessor.replacements(text) {
+        if range.start < last
+            || range.end < range.start
+            || !text.is_char_boundary(range.start)
+            || !text.is_char_boundary(range.end)
+        {
+            continue;
+        }
+        result.push_str(&text[last..range.start]);
+        offsets.push(
+            result.len()..result.len() + replacement.len(),
+            range.clone(),
+        );
+        result.push_str(&replacement);
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(normalizer: Normalizer, text: &str) -> String {
+        let mut result = String::new();
+        preprocess(&normalizer, text, &mut result, &mut OffsetMap::default());
+        result
+    }
+
+    #[test]
+    fn test_entities() {
+        let text = "Mail jane.doe@example.com or @support_team, see https://ex.com/kill?a=1.";
+        assert_eq!(
+            normalize(Normalizer::Urls(Redaction::Mask), text),
+            "Mail jane.doe@example.com or @support_team, see <url>."
+        );
+        assert_eq!(
+            normalize(Normalizer::Emails(Redaction::Strip), text),
+            "Mail   or @support_team, see https://ex.com/kill?a=1."
+        );
+        assert_eq!(
+            normalize(Normalizer::Mentions(Redaction::Mask), text),
+            "Mail jane.doe@example.com or <mention>, see https://ex.com/kill?a=1."
+        );
+        assert_eq!(
+            normalize(Normalizer::Urls(Redaction::Strip), "(www.love.com) <3"),
+            "( ) <3"
+        );
+        assert_eq!(
+            normalize(
+                Normalizer::Urls(Redaction::Mask),
+                "(see https://en.wikipedia.org/wiki/Rust_(programming_language)). Great"
+            ),
+            "(see <url>). Great"
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        // Steps run in order, so Html and Markdown have to run before the others
+        let run = |steps: &[Normalizer], text: &str| {
+            steps
+                .iter()
+                .fold(text.to_owned(), |text, &step| normalize(step, &text))
+        };
+        let urls = Normalizer::Urls(Redaction::Mask);
+        let text = "<a href=\"https://x.com/kill\">Great</a>";
+        assert_eq!(run(&[Normalizer::Html, urls], text), " Great ");
+        assert_eq!(run(&[urls, Normalizer::Html], text), "<a href=\" \">Great ");
+
+        let text = "**https://x.com/kill**";
+        assert_eq!(run(&[Normalizer::Markdown, urls], text), "<url>");
+        assert_eq!(run(&[urls, Normalizer::Markdown], text), "**<url>");
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(
+            normalize(
+                Normalizer::Html,
+                "<p class=\"x\">Fish &amp; chips</p><script>kill()</script><!-- hate -->&#233;</3"
+            ),
+            " Fish & chips   é</3"
+        );
+        assert_eq!(
+            normalize(
+                Normalizer::Markdown,
+                "# Title\n> **Great** _work_, see [the *best*](https://x.com/hate) `kill` snake_case :*"
+            ),
+            "Title\nGreat work, see the best   snake_case :*"
+        );
+    }
+}
@@ -1,20 +1,35 @@
-use std::cmp::min;
+use std::{borrow::Cow, cmp::min, fmt, ops::Range, sync::Arc};
 
 use crate::{
+    config::{Elongation, IdiomMatching, ScoringConfig},
+    document::{split_sentences, DocumentSentiment, SentenceSentiment},
+    explanation::{Adjustment, Explanation, Rule, TokenExplanation},
+    lexicon::{EmojiLexicon, Lexicon, LexiconOverlay, OverlayEntry},
+    negation::NegationDetector,
+    offsets::OffsetMap,
     parsed_text::ParsedText,
+    phrases::PhraseTable,
+    preprocess::{preprocess, Preprocessor},
     static_resources::{
-        BOOSTER_DICT, BOOSTER_DICT_EARLY_RETURN, C_INCR, EMOJI_LEXICON, LEXICON, NEGATION_SCALAR,
-        SPECIAL_CASE_EARLY_RETURN, SPECIAL_CASE_IDIOMS, STATIC_AT, STATIC_BUT, STATIC_DOUBT,
-        STATIC_KIND, STATIC_LEAST, STATIC_NEVER, STATIC_OF, STATIC_SO, STATIC_THIS, STATIC_VERY,
-        STATIC_WITHOUT,
+        booster_dict, BOOSTER_DICT, B_DECR, B_INCR, CONTRASTIVE_MARKERS, EMOJI_LEXICON, LEXICON,
+        NEGATIONS, SENTIMENT_LADEN_IDIOMS, SPECIAL_CASE_IDIOMS, STATIC_AT, STATIC_DOUBT,
+        STATIC_LEAST, STATIC_NEVER, STATIC_SO, STATIC_THIS, STATIC_VERY, STATIC_WITHOUT,
+    },
+    tokenizer::{DefaultTokenizer, Emoticons, Tokenizer},
+    util::{
+        elongated_words, has_elongation, is_all_caps, normalize_score, scalar_inc_dec,
+        sum_sentiment_scores, unelongated_forms,
     },
-    util::{is_all_caps, is_negated, normalize_score, scalar_inc_dec, sum_sentiment_scores},
 };
-use hashbrown::HashMap;
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use unicase::UniCase;
+use unicode_segmentation::UnicodeSegmentation;
 
-/// Return value of the `polarity_scores` method
+/// Return value of the `polarity_scores` method. Serializes with the same keys as the
+/// dictionary returned by the original Python implementation.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SentimentIntensity {
     pub neg: f64,
     pub neu: f64,
@@ -24,25 +39,364 @@ pub struct SentimentIntensity {
 
 #[derive(Debug, Clone)]
 pub struct SentimentIntensityAnalyzer<'a> {
-    lexicon: &'a HashMap<UniCase<&'a str>, f64>,
-    emoji_lexicon: &'a HashMap<&'a str, &'a str>,
+    lexicon: Cow<'a, Lexicon<'a>>,
+    tokenizer: Option<Shared<dyn Tokenizer + 'a>>,
+    preprocessors: Vec<Shared<dyn Preprocessor + 'a>>,
+    // Splits the emoticons of the lexicon, overlays and emoji valences from words, unless another
+    // tokenizer is used. Its emoticons also end sentences.
+    default_tokenizer: DefaultTokenizer,
+    overlays: Vec<Cow<'a, LexiconOverlay<'a>>>,
+    emoji_lexicon: Cow<'a, EmojiLexicon<'a>>,
+    emoji_valences: Option<Cow<'a, Lexicon<'a>>>,
+    special_idioms: Cow<'a, PhraseTable<'a>>,
+    contrastive_markers: Cow<'a, PhraseTable<'a, Contrast>>,
+    boosters: Cow<'a, PhraseTable<'a>>,
+    negation_detector: Option<Shared<dyn NegationDetector + 'a>>,
+    idiom_matching: IdiomMatching,
+    elongation: Elongation,
+    config: ScoringConfig,
+}
+
+// A shared negation detector, tokenizer or preprocessor, which is debug printed without its contents
+struct Shared<T: ?Sized>(Arc<T>);
+
+// Not derived, which would require `T: Clone`
+impl<T: ?Sized> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Shared").finish_non_exhaustive()
+    }
+}
+
+// Which words a contrastive marker scales
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Contrast {
+    // The words before and after the marker in its sentence, i.e. "but"
+    Around { before: f64, after: f64 },
+    // The clause the marker introduces, up to the next comma, semicolon, colon or dash, and the
+    // other words of its sentence, i.e. "although"
+    Clause { clause: f64, rest: f64 },
+}
+
+// How an emoji in the text is scored
+enum EmojiReplacement<'a> {
+    // As a token with the valence from the emoji valence lexicon
+    Token,
+    // As the words of its description
+    Description(&'a str),
+}
+
+// Buffers reused between calls when scoring many texts
+#[derive(Default)]
+struct Scratch {
+    rewritten: Rewritten,
+    sentiments: Vec<f64>,
+}
+
+// A text after preprocessing, replacing emoji and normalizing elongated words, with how to map
+// offsets back after every step
+#[derive(Default)]
+struct Rewritten {
+    text: String,
+    buffer: String,
+    offsets: Vec<OffsetMap>,
+    // Ranges of the text that replace elongated words
+    elongated: Vec<Range<usize>>,
+}
+
+impl Rewritten {
+    // Maps a range of the rewritten text back to the text before the first step
+    fn original_span(&self, span: &Range<usize>) -> Range<usize> {
+        self.offsets
+            .iter()
+            .rev()
+            .fold(span.clone(), |span, offsets| offsets.map(&span))
+    }
 }
 
-impl SentimentIntensityAnalyzer<'_> {
+/// Builds a `SentimentIntensityAnalyzer` with custom lexicons.
+///
+/// Anything that is not set falls back to the lexicons shipped with VADER. Lexicons can be passed
+/// by reference or by value; an analyzer built only from owned lexicons is `'static`.
+///
+/// ```
+/// use vader_sentimental::{Lexicon, SentimentIntensityAnalyzer};
+///
+/// let lexicon: Lexicon = [("bullish", 2.0), ("bearish", -2.0)].into_iter().collect();
+/// let analyzer = SentimentIntensityAnalyzer::builder().lexicon(lexicon).build();
+/// assert!(analyzer.polarity_scores("Analysts are bullish").compound > 0.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SentimentIntensityAnalyzerBuilder<'a> {
+    lexicon: Option<Cow<'a, Lexicon<'a>>>,
+    overlays: Vec<Cow<'a, LexiconOverlay<'a>>>,
+    emoji_lexicon: Option<Cow<'a, EmojiLexicon<'a>>>,
+    emoji_valences: Option<Cow<'a, Lexicon<'a>>>,
+    special_idioms: Option<PhraseTable<'a>>,
+    contrastive_markers: Option<PhraseTable<'a, Contrast>>,
+    // Boosters to add, with their scalar, or to remove from the built-in ones
+    booster_edits: Vec<(Cow<'a, str>, Option<f64>)>,
+    negation_detector: Option<Shared<dyn NegationDetector + 'a>>,
+    tokenizer: Option<Shared<dyn Tokenizer + 'a>>,
+    preprocessors: Vec<Shared<dyn Preprocessor + 'a>>,
+    idiom_matching: IdiomMatching,
+    elongation: Elongation,
+    config: ScoringConfig,
+}
+
+impl<'a> SentimentIntensityAnalyzerBuilder<'a> {
+    /// Replaces the word valence lexicon
     #[must_use]
-    pub fn new() -> SentimentIntensityAnalyzer<'static> {
+    pub fn lexicon(mut self, lexicon: impl Into<Cow<'a, Lexicon<'a>>>) -> Self {
+        self.lexicon = Some(lexicon.into());
+        self
+    }
+
+    /// Layers an overlay on top of the lexicon. Overlays added later take precedence over
+    /// earlier ones.
+    #[must_use]
+    pub fn overlay(mut self, overlay: impl Into<Cow<'a, LexiconOverlay<'a>>>) -> Self {
+        self.overlays.push(overlay.into());
+        self
+    }
+
+    /// Replaces the lexicon of emoji descriptions
+    #[must_use]
+    pub fn emoji_lexicon(mut self, emoji_lexicon: impl Into<Cow<'a, EmojiLexicon<'a>>>) -> Self {
+        self.emoji_lexicon = Some(emoji_lexicon.into());
+        self
+    }
+
+    /// Scores the emoji in this lexicon as single tokens with their own valence, instead of
+    /// replacing them with their description. Emoji that are not in it are still replaced with
+    /// their description from the emoji lexicon.
+    ///
+    /// ```
+    /// use vader_sentimental::{Lexicon, SentimentIntensityAnalyzer};
+    ///
+    /// let emoji_valences: Lexicon = [("😂", 2.5)].into_iter().collect();
+    /// let analyzer = SentimentIntensityAnalyzer::builder()
+    ///     .emoji_valences(emoji_valences)
+    ///     .build();
+    /// assert!(analyzer.polarity_scores("😂").compound > 0.0);
+    /// ```
+    #[must_use]
+    pub fn emoji_valences(mut self, emoji_valences: impl Into<Cow<'a, Lexicon<'a>>>) -> Self {
+        self.emoji_valences = Some(emoji_valences.into());
+        self
+    }
+
+    /// Adds a special case idiom, i.e. "could be worse", or replaces the valence of a built-in
+    /// one. The valence of the idiom replaces the valence of the sentiment words it contains.
+    ///
+    /// If several idioms match around a word, the idiom with the most words wins, and idioms with
+    /// the same number of words are tried in alphabetical order, so "not bad at all" wins over
+    /// "not bad" regardless of the order they were added in.
+    #[must_use]
+    pub fn special_idiom(mut self, idiom: impl Into<Cow<'a, str>>, valence: f64) -> Self {
+        self.special_idioms_mut().insert(idiom, valence);
+        self
+    }
+
+    /// Removes a special case idiom, including built-in ones like "yeah right"
+    #[must_use]
+    pub fn remove_special_idiom(mut self, idiom: &str) -> Self {
+        self.special_idioms_mut().remove(idiom);
+        self
+    }
+
+    fn special_idioms_mut(&mut self) -> &mut PhraseTable<'a> {
+        self.special_idioms
+            .get_or_insert_with(|| SPECIAL_CASE_IDIOMS.clone())
+    }
+
+    /// Adds a contrastive marker like "on the other hand", or replaces a built-in one like "but".
+    /// The valences of the words before the marker are multiplied by `before`, and those after it
+    /// by `after`, within the sentence of the marker. A sentence that starts with a marker also
+    /// scales the sentence before it. A marker at the end of a sentence, like in "it works,
+    /// however", scales nothing. The built-in "but", "however" and "nevertheless" use 0.5 and 1.5.
+    /// "yet" is not built in, as it is mostly an adverb in "not yet" or "yet another".
+    #[must_use]
+    pub fn contrastive_marker(
+        mut self,
+        marker: impl Into<Cow<'a, str>>,
+        before: f64,
+        after: f64,
+    ) -> Self {
+        self.contrastive_markers_mut()
+            .insert(marker, Contrast::Around { before, after });
+        self
+    }
+
+    /// Adds a marker like "whereas" that introduces a clause of less weight, or replaces a
+    /// built-in one like "although". The valences of the words of the clause, up to the next
+    /// comma, semicolon, colon or dash in the sentence, are multiplied by `clause`, and those of
+    /// the other words of the sentence by `rest`. The built-in "although", "though" and "even
+    /// though" use 0.5 and 1.5.
+    #[must_use]
+    pub fn concessive_marker(
+        mut self,
+        marker: impl Into<Cow<'a, str>>,
+        clause: f64,
+        rest: f64,
+    ) -> Self {
+        self.contrastive_markers_mut()
+            .insert(marker, Contrast::Clause { clause, rest });
+        self
+    }
+
+    /// Removes a contrastive or concessive marker, including built-in ones like "but"
+    #[must_use]
+    pub fn remove_contrastive_marker(mut self, marker: &str) -> Self {
+        self.contrastive_markers_mut().remove(marker);
+        self
+    }
+
+    fn contrastive_markers_mut(&mut self) -> &mut PhraseTable<'a, Contrast> {
+        self.contrastive_markers
+            .get_or_insert_with(|| CONTRASTIVE_MARKERS.clone())
+    }
+
+    /// Adds a booster, i.e. "mega" or "kind of", or replaces the scalar of a built-in one. The
+    /// scalar is added to the valence of the following sentiment word, and subtracted if that word
+    /// is negative; dampeners have a negative scalar.
+    ///
+    /// If boosters overlap, the booster that starts first wins, and of the boosters starting at the
+    /// same word the one with the most words, so "sort of" wins over "sort".
+    #[must_use]
+    pub fn booster(mut self, word: impl Into<Cow<'a, str>>, scalar: f64) -> Self {
+        self.booster_edits.push((word.into(), Some(scalar)));
+        self
+    }
+
+    /// Removes a booster, including built-in ones like "very"
+    #[must_use]
+    pub fn remove_booster(mut self, word: &str) -> Self {
+        self.booster_edits.push((Cow::Owned(word.to_owned()), None));
+        self
+    }
+
+    /// Replaces the detection of negations such as "not", e.g. with a custom `Negations` list
+    #[must_use]
+    pub fn negation_detector(mut self, detector: impl NegationDetector + 'a) -> Self {
+        self.negation_detector = Some(Shared(Arc::new(detector)));
+        self
+    }
+
+    /// Replaces the tokenizer, e.g. with one for hashtags or for text that was tokenized before
+    #[must_use]
+    pub fn tokenizer(mut self, tokenizer: impl Tokenizer + 'a) -> Self {
+        self.tokenizer = Some(Shared(Arc::new(tokenizer)));
+        self
+    }
+
+    /// Adds a step that rewrites texts before they are tokenized, e.g. a `Normalizer` that
+    /// removes URLs. Steps run in the order they are added.
+    #[must_use]
+    pub fn preprocessor(mut self, preprocessor: impl Preprocessor + 'a) -> Self {
+        self.preprocessors.push(Shared(Arc::new(preprocessor)));
+        self
+    }
+
+    /// Changes how special case idioms and boosters are found around sentiment words.
+    /// `IdiomMatching::Substring` restores the idiom and booster matching of earlier versions.
+    #[must_use]
+    pub fn idiom_matching(mut self, matching: IdiomMatching) -> Self {
+        self.idiom_matching = matching;
+        self
+    }
+
+    /// Changes how elongated words like "goooood" are scored. `Elongation::Ignore` turns their
+    /// normalization off.
+    #[must_use]
+    pub fn elongation(mut self, elongation: Elongation) -> Self {
+        self.elongation = elongation;
+        self
+    }
+
+    /// Replaces the constants used to scale and amplify sentiment
+    #[must_use]
+    pub fn scoring_config(mut self, config: ScoringConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    #[must_use]
+    #[allow(clippy::float_cmp)]
+    pub fn build(self) -> SentimentIntensityAnalyzer<'a> {
+        let config = self.config;
+        let mut boosters = if config.b_incr == B_INCR && config.b_decr == B_DECR {
+            Cow::Borrowed(&*BOOSTER_DICT)
+        } else {
+            Cow::Owned(booster_dict(config.b_incr, config.b_decr))
+        };
+        for (word, scalar) in self.booster_edits {
+            match scalar {
+                Some(scalar) => boosters.to_mut().insert(word, scalar),
+                None => {
+                    boosters.to_mut().remove(&word);
+                }
+            }
+        }
+        let custom_words =
+            self.lexicon.is_some() || !self.overlays.is_empty() || self.emoji_valences.is_some();
+        let default_tokenizer = if custom_words {
+            let lexicon = self.lexicon.as_deref().unwrap_or(&LEXICON);
+            let emoji_valences = self.emoji_valences.as_deref().into_iter();
+            let words = lexicon
+                .iter()
+                .chain(emoji_valences.flat_map(Lexicon::iter))
+                .map(|(word, _)| word)
+                .chain(self.overlays.iter().flat_map(|overlay| overlay.words()));
+            DefaultTokenizer::with_words(words)
+        } else {
+            DefaultTokenizer::new()
+        };
         SentimentIntensityAnalyzer {
-            lexicon: &LEXICON,
-            emoji_lexicon: &EMOJI_LEXICON,
+            lexicon: self.lexicon.unwrap_or(Cow::Borrowed(&*LEXICON)),
+            tokenizer: self.tokenizer,
+            preprocessors: self.preprocessors,
+            default_tokenizer,
+            overlays: self.overlays,
+            emoji_lexicon: self.emoji_lexicon.unwrap_or(Cow::Borrowed(&*EMOJI_LEXICON)),
+            emoji_valences: self.emoji_valences,
+            special_idioms: self
+                .special_idioms
+                .map_or(Cow::Borrowed(&*SPECIAL_CASE_IDIOMS), Cow::Owned),
+            contrastive_markers: self
+                .contrastive_markers
+                .map_or(Cow::Borrowed(&*CONTRASTIVE_MARKERS), Cow::Owned),
+            boosters,
+            negation_detector: self.negation_detector,
+            idiom_matching: self.idiom_matching,
+            elongation: self.elongation,
+            config,
         }
     }
+}
+
+impl<'a> SentimentIntensityAnalyzer<'a> {
+    #[must_use]
+    pub fn new() -> SentimentIntensityAnalyzer<'static> {
+        SentimentIntensityAnalyzer::builder().build()
+    }
+
+    #[must_use]
+    pub fn builder() -> SentimentIntensityAnalyzerBuilder<'a> {
+        SentimentIntensityAnalyzerBuilder::default()
+    }
 
     #[must_use]
     #[allow(clippy::similar_names)]
     #[allow(clippy::unused_self)]
     fn get_total_sentiment(
         &self,
-        sentiments: Vec<f64>,
+        sentiments: &[f64],
         punct_emph_amplifier: f64,
     ) -> SentimentIntensity {
         let (mut neg, mut neu, mut pos, mut compound) = (0f64, 0f64, 0f64, 0f64);
@@ -53,7 +407,7 @@ impl SentimentIntensityAnalyzer<'_> {
             } else {
                 total_sentiment -= punct_emph_amplifier;
             }
-            compound = normalize_score(total_sentiment);
+            compound = normalize_score(total_sentiment, self.config.normalization_alpha);
 
             let (mut pos_sum, mut neg_sum, neu_count) = sum_sentiment_scores(sentiments);
 
@@ -78,90 +432,518 @@ impl SentimentIntensityAnalyzer<'_> {
     }
 
     #[must_use]
-    #[allow(clippy::if_same_then_else)]
     pub fn polarity_scores(&self, text: &str) -> SentimentIntensity {
-        let text = self.append_emoji_descriptions(text);
-        let parsedtext = ParsedText::from_text(&text);
+        self.polarity_scores_with(text, &mut Scratch::default())
+    }
+
+    fn polarity_scores_with(&self, text: &str, scratch: &mut Scratch) -> SentimentIntensity {
+        self.rewrite(text, &mut scratch.rewritten);
+        self.score_rewritten(scratch)
+    }
+
+    // Scores the text in `scratch.rewritten`
+    fn score_rewritten(&self, scratch: &mut Scratch) -> SentimentIntensity {
+        let parsedtext =
+            ParsedText::from_text(&scratch.rewritten.text, self.tokenizer(), &self.config);
+        self.token_sentiments(
+            &parsedtext,
+            &scratch.rewritten.elongated,
+            None,
+            &mut scratch.sentiments,
+        );
+        self.get_total_sentiment(&scratch.sentiments, parsedtext.punc_amplifier)
+    }
+
+    /// Scores many texts in parallel, in the same order as the input
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn polarity_scores_batch(&self, texts: &[&str]) -> Vec<SentimentIntensity> {
+        self.polarity_scores_par_iter(texts).collect()
+    }
+
+    /// Scores the texts of a parallel iterator, reusing buffers within each rayon job
+    #[cfg(feature = "rayon")]
+    pub fn polarity_scores_par_iter<'s, I>(
+        &'s self,
+        texts: I,
+    ) -> impl ParallelIterator<Item = SentimentIntensity> + use<'s, 'a, I>
+    where
+        I: IntoParallelIterator,
+        I::Item: AsRef<str>,
+    {
+        texts
+            .into_par_iter()
+            .map_init(Scratch::default, |scratch, text| {
+                self.polarity_scores_with(text.as_ref(), scratch)
+            })
+    }
+
+    /// Splits a longer text into sentences and scores every sentence on its own, so rules like
+    /// "but" only affect the sentence they appear in. The document scores are the mean of the
+    /// sentence scores.
+    ///
+    /// The preprocessors run on the whole text before it is split, so code blocks or scripts they
+    /// remove don't end sentences.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn polarity_scores_document(&self, text: &str) -> DocumentSentiment {
+        let mut document = Rewritten::default();
+        self.preprocess(text, &mut document);
+        let mut scratch = Scratch::default();
+        let sentences: Vec<SentenceSentiment> =
+            split_sentences(&document.text, self.default_tokenizer.emoticons())
+                .into_iter()
+                .map(|range| {
+                    scratch.rewritten.text.clear();
+                    scratch
+                        .rewritten
+                        .text
+                        .push_str(&document.text[range.clone()]);
+                    self.describe(&mut scratch.rewritten);
+                    SentenceSentiment {
+                        scores: self.score_rewritten(&mut scratch),
+                        range: document.original_span(&range),
+                    }
+                })
+                .collect();
+
+        let mut scores = SentimentIntensity {
+            neg: 0f64,
+            neu: 0f64,
+            pos: 0f64,
+            compound: 0f64,
+        };
+        if !sentences.is_empty() {
+            for sentence in &sentences {
+                scores.neg += sentence.scores.neg;
+                scores.neu += sentence.scores.neu;
+                scores.pos += sentence.scores.pos;
+                scores.compound += sentence.scores.compound;
+            }
+            let count = sentences.len() as f64;
+            scores.neg /= count;
+            scores.neu /= count;
+            scores.pos /= count;
+            scores.compound /= count;
+        }
+        DocumentSentiment { sentences, scores }
+    }
+
+    /// Scores a text like `polarity_scores`, and reports how every token contributed to the
+    /// scores and which rules adjusted its valence
+    #[must_use]
+    pub fn explain(&self, text: &str) -> Explanation {
+        let mut rewritten = Rewritten::default();
+        self.rewrite(text, &mut rewritten);
+        let parsedtext = ParsedText::from_text(&rewritten.text, self.tokenizer(), &self.config);
+        let mut adjustments = vec![Vec::new(); parsedtext.tokens.len()];
+        let mut sentiments = Vec::new();
+        self.token_sentiments(
+            &parsedtext,
+            &rewritten.elongated,
+            Some(&mut adjustments),
+            &mut sentiments,
+        );
+
+        let tokens = parsedtext
+            .tokens
+            .iter()
+            .enumerate()
+            .zip(adjustments)
+            .zip(&sentiments)
+            .map(|(((i, word), adjustments), valence)| TokenExplanation {
+                token: (*word).to_string(),
+                span: rewritten.original_span(&parsedtext.span(i)),
+                lexicon_valence: self.word_valence(word),
+                adjustments,
+                valence: *valence,
+            })
+            .collect();
+        Explanation {
+            tokens,
+            punctuation_amplifier: parsedtext.punc_amplifier,
+            scores: self.get_total_sentiment(&sentiments, parsedtext.punc_amplifier),
+        }
+    }
+
+    // Computes the valence of every token, recording the applied rules per token if requested.
+    // `elongated` are the ranges of the text that replace elongated words.
+    fn token_sentiments(
+        &self,
+        parsedtext: &ParsedText,
+        elongated: &[Range<usize>],
+        mut trace: Option<&mut [Vec<Adjustment>]>,
+        sentiments: &mut Vec<f64>,
+    ) {
         let tokens = &parsedtext.tokens;
-        let mut sentiments = Vec::with_capacity(tokens.len());
+        sentiments.clear();
+        sentiments.reserve(tokens.len());
 
         for (i, word) in tokens.iter().enumerate() {
-            if BOOSTER_DICT.contains_key(word) {
-                sentiments.push(0f64);
-            } else if i < tokens.len() - 1 && word == &*STATIC_KIND && tokens[i + 1] == *STATIC_OF {
+            let token_trace = trace.as_deref_mut().map(|t| &mut t[i]);
+            // Boosters, including the words of phrases like "kind of"
+            if self.boosters.match_around(tokens, i).is_some() {
+                let valence = self.word_valence(word).unwrap_or(0f64);
+                record(token_trace, Rule::BoosterWord, valence, 0f64);
                 sentiments.push(0f64);
             } else {
-                sentiments.push(self.sentiment_valence(&parsedtext, word, i));
+                let span = parsedtext.span(i);
+                let emphasized = self.elongation == Elongation::Emphasize
+                    && elongated
+                        .iter()
+                        .any(|range| range.start < span.end && span.start < range.end);
+                sentiments.push(self.sentiment_valence(
+                    parsedtext,
+                    word,
+                    i,
+                    emphasized,
+                    token_trace,
+                ));
+            }
+        }
+
+        idioms_check(
+            tokens,
+            sentiments,
+            self.negations(),
+            self.config.negation_scalar,
+            trace.as_deref_mut(),
+        );
+
+        contrast_check(
+            parsedtext,
+            sentiments,
+            &self.contrastive_markers,
+            self.default_tokenizer.emoticons(),
+            trace,
+        );
+    }
+
+    // Runs the preprocessors, replaces emoji with their description and normalizes elongated
+    // words
+    fn rewrite(&self, text: &str, rewritten: &mut Rewritten) {
+        self.preprocess(text, rewritten);
+        self.describe(rewritten);
+    }
+
+    // Runs the preprocessors on the text, into `rewritten.text`
+    fn preprocess(&self, text: &str, rewritten: &mut Rewritten) {
+        rewritten
+            .offsets
+            .resize_with(self.preprocessors.len() + 2, OffsetMap::default);
+        rewritten.text.clear();
+        rewritten.text.push_str(text);
+        for (Shared(preprocessor), offsets) in self.preprocessors.iter().zip(&mut rewritten.offsets)
+        {
+            preprocess(
+                preprocessor.as_ref(),
+                &rewritten.text,
+                &mut rewritten.buffer,
+                offsets,
+            );
+            std::mem::swap(&mut rewritten.text, &mut rewritten.buffer);
+        }
+    }
+
+    // Replaces emoji in the preprocessed `rewritten.text` with their description and normalizes
+    // elongated words
+    fn describe(&self, rewritten: &mut Rewritten) {
+        rewritten
+            .offsets
+            .resize_with(self.preprocessors.len() + 2, OffsetMap::default);
+        rewritten.elongated.clear();
+        let (emoji_offsets, elongation_offsets) =
+            rewritten.offsets[self.preprocessors.len()..].split_at_mut(1);
+        std::mem::swap(&mut rewritten.text, &mut rewritten.buffer);
+        self.write_emoji_descriptions(
+            &rewritten.buffer,
+            &mut rewritten.text,
+            &mut emoji_offsets[0],
+        );
+        elongation_offsets[0].clear();
+        if self.elongation != Elongation::Ignore && has_elongation(&rewritten.text) {
+            std::mem::swap(&mut rewritten.text, &mut rewritten.buffer);
+            self.normalize_elongations(
+                &rewritten.buffer,
+                &mut rewritten.text,
+                &mut elongation_offsets[0],
+                &mut rewritten.elongated,
+            );
+        }
+    }
+
+    // Replaces elongated words with the word they elongate, i.e. "goooood" with "good", if only the
+    // shorter word is known. The ranges of the replacements are added to `elongated`.
+    fn normalize_elongations(
+        &self,
+        text: &str,
+        result: &mut String,
+        offsets: &mut OffsetMap,
+        elongated: &mut Vec<Range<usize>>,
+    ) {
+        result.clear();
+        let mut last = 0;
+        for word in elongated_words(text) {
+            if self.is_known(&text[word.clone()]) {
+                continue;
             }
+            let Some(form) = unelongated_forms(&text[word.clone()])
+                .into_iter()
+                .find(|form| self.is_known(form))
+            else {
+                continue;
+            };
+            result.push_str(&text[last..word.start]);
+            let replacement = result.len()..result.len() + form.len();
+            offsets.push(replacement.clone(), word.clone());
+            elongated.push(replacement);
+            result.push_str(&form);
+            last = word.end;
         }
-        but_check(tokens, &mut sentiments);
-        self.get_total_sentiment(sentiments, parsedtext.punc_amplifier)
+        result.push_str(&text[last..]);
+    }
+
+    // Whether a word has a valence, is a booster or a negation
+    fn is_known(&self, word: &str) -> bool {
+        let word = UniCase::new(word);
+        self.word_valence(&word).is_some()
+            || self.boosters.contains_word(&word)
+            || self.negations().is_negation(&[word], 0)
     }
 
     //Removes emoji and appends their description to the end the input text
     #[must_use]
     pub fn append_emoji_descriptions(&self, text: &str) -> String {
         let mut result = String::new();
+        self.write_emoji_descriptions(text, &mut result, &mut OffsetMap::default());
+        result
+    }
+
+    // Matches emoji per extended grapheme cluster, so sequences joined by zero width joiners,
+    // skin tone modifiers, flags and keycaps resolve to their own description. A cluster that is
+    // not in the lexicon falls back to its longest known prefix, and so on for the rest of it.
+    // Emoji with a valence of their own are kept, separated by spaces so they become a token, and
+    // descriptions are separated from words right after them.
+    fn write_emoji_descriptions(&self, text: &str, result: &mut String, offsets: &mut OffsetMap) {
+        result.clear();
+        offsets.clear();
         let mut prev_space = true;
-        for chr in text.chars() {
-            let mut my_buf: [u8; 4] = [0; 4];
-            let cheap_str: &str = chr.encode_utf8(&mut my_buf);
-            if let Some(chr_replacement) = self.emoji_lexicon.get(&cheap_str) {
-                if !prev_space {
-                    result.push(' ');
+        // Whether the last character was an emoji, which needs a space before a word after it
+        let mut after_emoji = false;
+        let push_space = |result: &mut String, offsets: &mut OffsetMap, at: usize| {
+            offsets.push(result.len()..result.len() + 1, at..at);
+            result.push(' ');
+        };
+        for (cluster_start, cluster) in text.grapheme_indices(true) {
+            let mut rest = cluster;
+            while !rest.is_empty() {
+                let at = cluster_start + cluster.len() - rest.len();
+                let emoji = if rest.is_ascii() {
+                    None
+                } else {
+                    self.longest_emoji(rest)
+                };
+                if let Some((len, replacement)) = emoji {
+                    if !prev_space {
+                        push_space(result, offsets, at);
+                    }
+                    match replacement {
+                        EmojiReplacement::Token => result.push_str(&rest[..len]),
+                        EmojiReplacement::Description(description) => {
+                            offsets
+                                .push(result.len()..result.len() + description.len(), at..at + len);
+                            result.push_str(description);
+                        }
+                    }
+                    prev_space = false;
+                    after_emoji = true;
+                    rest = &rest[len..];
+                } else {
+                    let chr = rest.chars().next().unwrap_or_default();
+                    if after_emoji && chr.is_alphanumeric() {
+                        push_space(result, offsets, at);
+                    }
+                    prev_space = chr == ' ';
+                    after_emoji = false;
+                    result.push(chr);
+                    rest = &rest[chr.len_utf8()..];
                 }
-                result.push_str(chr_replacement);
-                prev_space = false;
-            } else {
-                prev_space = chr == ' ';
-                result.push(chr);
             }
         }
-        result
     }
 
-    fn sentiment_valence(&self, parsed: &ParsedText, word: &UniCase<&str>, i: usize) -> f64 {
+    // Finds the longest emoji at the start of a grapheme cluster, with its length in bytes
+    fn longest_emoji(&self, cluster: &str) -> Option<(usize, EmojiReplacement<'_>)> {
+        cluster
+            .char_indices()
+            .map(|(i, chr)| i + chr.len_utf8())
+            .rev()
+            .find_map(|len| {
+                let emoji = &cluster[..len];
+                if self.has_emoji_valence(emoji) {
+                    Some((len, EmojiReplacement::Token))
+                } else {
+                    let description = self.emoji_lexicon.get(emoji)?;
+                    Some((len, EmojiReplacement::Description(description)))
+                }
+            })
+    }
+
+    fn has_emoji_valence(&self, emoji: &str) -> bool {
+        self.emoji_valences
+            .as_ref()
+            .is_some_and(|valences| valences.contains(emoji))
+    }
+
+    fn negations(&self) -> &dyn NegationDetector {
+        match &self.negation_detector {
+            Some(Shared(detector)) => detector.as_ref(),
+            None => &*NEGATIONS,
+        }
+    }
+
+    fn tokenizer(&self) -> &dyn Tokenizer {
+        match &self.tokenizer {
+            Some(Shared(tokenizer)) => tokenizer.as_ref(),
+            None => &self.default_tokenizer,
+        }
+    }
+
+    // Looks up a word in the overlays, most recently added first, and then in the lexicon
+    fn word_valence(&self, word: &UniCase<&str>) -> Option<f64> {
+        match self.overlays.iter().rev().find_map(|o| o.entry(word)) {
+            Some(OverlayEntry::Valence(valence)) => Some(valence),
+            Some(OverlayEntry::Suppressed) => None,
+            None => self.lexicon.valence(word).or_else(|| {
+                let emoji_valences = self.emoji_valences.as_ref()?;
+                emoji_valences.valence(word)
+            }),
+        }
+    }
+
+    fn sentiment_valence(
+        &self,
+        parsed: &ParsedText,
+        word: &UniCase<&str>,
+        i: usize,
+        emphasized: bool,
+        mut trace: Option<&mut Vec<Adjustment>>,
+    ) -> f64 {
         let mut valence = 0f64;
         let tokens = &parsed.tokens;
-        if let Some(word_valence) = self.lexicon.get(word) {
-            valence = *word_valence;
-            if is_all_caps(word) && parsed.has_mixed_caps {
+        if let Some(word_valence) = self.word_valence(word) {
+            valence = word_valence;
+            // Emoji match the all caps pattern, but are not shouted
+            if is_all_caps(word) && parsed.has_mixed_caps && !self.has_emoji_valence(word) {
+                let before = valence;
+                if valence > 0f64 {
+                    valence += self.config.c_incr;
+                } else {
+                    valence -= self.config.c_incr;
+                }
+                record(trace.as_deref_mut(), Rule::AllCaps, before, valence);
+            }
+            if emphasized {
+                let before = valence;
                 if valence > 0f64 {
-                    valence += C_INCR;
+                    valence += self.config.elongation_incr;
                 } else {
-                    valence -= C_INCR;
+                    valence -= self.config.elongation_incr;
                 }
+                record(trace.as_deref_mut(), Rule::Elongation, before, valence);
             }
             for start_i in 0..3 {
-                if i > start_i && !self.lexicon.contains_key(&tokens[i - start_i - 1]) {
-                    let mut s =
-                        scalar_inc_dec(&tokens[i - start_i - 1], valence, parsed.has_mixed_caps);
+                if i > start_i && self.word_valence(&tokens[i - start_i - 1]).is_none() {
+                    let distance = start_i + 1;
+                    let mut s = scalar_inc_dec(
+                        tokens,
+                        i - start_i - 1,
+                        valence,
+                        parsed.has_mixed_caps,
+                        &self.boosters,
+                        self.config.c_incr,
+                    );
                     if start_i == 1 {
                         s *= 0.95;
                     } else if start_i == 2 {
                         s *= 0.9;
                     }
+                    let before = valence;
                     valence += s;
-                    valence = negation_check(valence, tokens, start_i, i);
+                    record(
+                        trace.as_deref_mut(),
+                        Rule::Booster { distance },
+                        before,
+                        valence,
+                    );
+                    let before = valence;
+                    valence = negation_check(
+                        valence,
+                        tokens,
+                        start_i,
+                        i,
+                        self.negations(),
+                        self.config.negation_scalar,
+                    );
+                    record(
+                        trace.as_deref_mut(),
+                        Rule::Negation { distance },
+                        before,
+                        valence,
+                    );
                     if start_i == 2 {
-                        valence = special_idioms_check(valence, tokens, i);
+                        let before = valence;
+                        valence = special_idioms_check(
+                            valence,
+                            tokens,
+                            i,
+                            &self.special_idioms,
+                            &self.boosters,
+                            self.idiom_matching,
+                        );
+                        record(trace.as_deref_mut(), Rule::SpecialIdiom, before, valence);
                     }
                 }
             }
-            valence = least_check(valence, tokens, i);
+            if i > 0 && self.word_valence(&tokens[i - 1]).is_none() {
+                let before = valence;
+                valence = least_check(valence, tokens, i, self.config.negation_scalar);
+                record(trace, Rule::Least, before, valence);
+            }
         }
         valence
     }
 }
 
+// Records a rule in the explanation of a token, if it changed the valence
+#[allow(clippy::float_cmp)]
+fn record(trace: Option<&mut Vec<Adjustment>>, rule: Rule, before: f64, after: f64) {
+    if let Some(trace) = trace {
+        if before != after {
+            trace.push(Adjustment {
+                rule,
+                before,
+                after,
+            });
+        }
+    }
+}
+
 /**
  * Check for specific patterns or tokens, and modify sentiment as needed
  **/
-fn negation_check(valence: f64, tokens: &[UniCase<&str>], start_i: usize, i: usize) -> f64 {
+fn negation_check(
+    valence: f64,
+    tokens: &[UniCase<&str>],
+    start_i: usize,
+    i: usize,
+    negations: &dyn NegationDetector,
+    negation_scalar: f64,
+) -> f64 {
     let mut valence = valence;
     if start_i == 0 {
-        if is_negated(&tokens[i - start_i - 1]) {
-            valence *= NEGATION_SCALAR;
+        if negations.is_negation(tokens, i - start_i - 1) {
+            valence *= negation_scalar;
         }
     } else if start_i == 1 {
         if tokens[i - 2] == *STATIC_NEVER
@@ -170,74 +952,182 @@ fn negation_check(valence: f64, tokens: &[UniCase<&str>], start_i: usize, i: usi
             valence *= 1.25;
         } else if tokens[i - 2] == *STATIC_WITHOUT && tokens[i - 1] == *STATIC_DOUBT {
             valence *= 1.0;
-        } else if is_negated(&tokens[i - start_i - 1]) {
-            valence *= NEGATION_SCALAR;
+        } else if negations.is_negation(tokens, i - start_i - 1) {
+            valence *= negation_scalar;
         }
     } else if start_i == 2 {
-        if tokens[i - 3] == *STATIC_NEVER && tokens[i - 2] == *STATIC_SO
-            || tokens[i - 2] == *STATIC_THIS
-            || tokens[i - 1] == *STATIC_SO
-            || tokens[i - 1] == *STATIC_THIS
+        if tokens[i - 3] == *STATIC_NEVER
+            && (tokens[i - 2] == *STATIC_SO
+                || tokens[i - 2] == *STATIC_THIS
+                || tokens[i - 1] == *STATIC_SO
+                || tokens[i - 1] == *STATIC_THIS)
         {
             valence *= 1.25;
-        } else if tokens[i - 3] == *STATIC_WITHOUT && tokens[i - 2] == *STATIC_DOUBT
-            || tokens[i - 1] == *STATIC_DOUBT
+        } else if tokens[i - 3] == *STATIC_WITHOUT
+            && (tokens[i - 2] == *STATIC_DOUBT || tokens[i - 1] == *STATIC_DOUBT)
         {
             valence *= 1.0;
-        } else if is_negated(&tokens[i - start_i - 1]) {
-            valence *= NEGATION_SCALAR;
+        } else if negations.is_negation(tokens, i - start_i - 1) {
+            valence *= negation_scalar;
         }
     }
     valence
 }
 
-// If "but" is in the tokens, scales down the sentiment of words before "but" and
-// adds more emphasis to the words after
-#[allow(clippy::comparison_chain)]
-pub fn but_check(tokens: &[UniCase<&str>], sentiments: &mut [f64]) {
-    if let Some(but_index) = tokens.iter().position(|&s| s == *STATIC_BUT) {
-        for (i, sentiment) in sentiments.iter_mut().enumerate() {
-            if i < but_index {
-                *sentiment *= 0.5;
-            } else if i > but_index {
-                *sentiment *= 1.5;
+// Scales the sentiment of the words around contrastive markers such as "but" and "although".
+// Markers only affect their own sentence, and the scalars of several markers multiply.
+fn contrast_check(
+    parsed: &ParsedText,
+    sentiments: &mut [f64],
+    markers: &PhraseTable<Contrast>,
+    emoticons: &Emoticons,
+    mut trace: Option<&mut [Vec<Adjustment>]>,
+) {
+    let tokens = &parsed.tokens;
+    if !tokens.iter().any(|t| markers.contains_word(t)) {
+        return;
+    }
+    for scope in contrast_scopes(parsed, markers, emoticons) {
+        let mut i = scope.start;
+        while i < scope.end {
+            let Some((len, contrast)) = markers.longest_match(&tokens[i..scope.end]) else {
+                i += 1;
+                continue;
+            };
+            let mut scale = |range: Range<usize>, scalar: f64| {
+                for j in range {
+                    let before = sentiments[j];
+                    sentiments[j] *= scalar;
+                    let token_trace = trace.as_deref_mut().map(|t| &mut t[j]);
+                    record(
+                        token_trace,
+                        Rule::Contrast { marker: i },
+                        before,
+                        sentiments[j],
+                    );
+                }
+            };
+            let after = i + len;
+            // A marker at the end of a sentence, i.e. in "it works, however", contrasts nothing
+            if after < scope.end {
+                match contrast {
+                    Contrast::Around {
+                        before,
+                        after: scalar,
+                    } => {
+                        scale(scope.start..i, before);
+                        scale(after..scope.end, scalar);
+                    }
+                    Contrast::Clause { clause, rest } => {
+                        let clause_end = clause_end(parsed, after, scope.end);
+                        scale(scope.start..i, rest);
+                        scale(after..clause_end, clause);
+                        scale(clause_end..scope.end, rest);
+                    }
+                }
             }
+            i = after;
         }
     }
 }
 
-#[allow(clippy::if_same_then_else)]
-fn least_check(valence: f64, tokens: &[UniCase<&str>], i: usize) -> f64 {
+// Splits the tokens into sentences. A sentence that starts with a contrastive marker like "but"
+// is joined with the sentence before it, as in "Great screen. But the battery is awful".
+fn contrast_scopes(
+    parsed: &ParsedText,
+    markers: &PhraseTable<Contrast>,
+    emoticons: &Emoticons,
+) -> Vec<Range<usize>> {
+    let tokens = &parsed.tokens;
+    let mut scopes: Vec<Range<usize>> = Vec::new();
+    let mut end = 0;
+    for sentence in split_sentences(parsed.text, emoticons) {
+        let start = end;
+        while end < tokens.len() && parsed.span(end).start < sentence.end {
+            end += 1;
+        }
+        let contrasts_previous = matches!(
+            markers.longest_match(&tokens[start..end]),
+            Some((_, Contrast::Around { .. }))
+        );
+        match scopes.last_mut() {
+            _ if start == end => {}
+            Some(scope) if contrasts_previous => scope.end = end,
+            _ => scopes.push(start..end),
+        }
+    }
+    scopes
+}
+
+// Finds the end of the clause that starts at token `start`: the first token after a comma,
+// semicolon, colon or dash, or `end`
+fn clause_end(parsed: &ParsedText, start: usize, end: usize) -> usize {
+    (start + 1..end)
+        .find(|&j| {
+            parsed
+                .text
+                .get(parsed.span(j - 1).end..parsed.span(j).start)
+                .is_some_and(|gap| gap.contains([',', ';', ':', '-', '–', '—']))
+        })
+        .unwrap_or(end)
+}
+
+// Negates words after "least", except in "at least" and "very least"
+fn least_check(valence: f64, tokens: &[UniCase<&str>], i: usize, negation_scalar: f64) -> f64 {
     let mut valence = valence;
-    if i > 1
-        && tokens[i - 1] == *STATIC_LEAST
-        && tokens[i - 2] == *STATIC_AT
-        && tokens[i - 2] == *STATIC_VERY
-    {
-        valence *= NEGATION_SCALAR;
+    if i > 1 && tokens[i - 1] == *STATIC_LEAST {
+        if tokens[i - 2] != *STATIC_AT && tokens[i - 2] != *STATIC_VERY {
+            valence *= negation_scalar;
+        }
     } else if i > 0 && tokens[i - 1] == *STATIC_LEAST {
-        valence *= NEGATION_SCALAR;
+        valence *= negation_scalar;
     }
     valence
 }
 
-// //This was present in the original python implementation, but unused
-// fn idioms_check(valence: f64, text: &str) -> f64 {
-//     let mut total_valence = 0f64;
-//     let mut count = 0;
-//     for (idiom, val) in SENTIMENT_LADEN_IDIOMS.iter() {
-//         if text.contains(idiom) {
-//             total_valence += val;
-//             count += 1;
-//         }
-//     }
-//     if count > 0 {
-//         return total_valence / count as f64;
-//     }
-//     0f64
-// }
-
-fn special_idioms_check(valence: f64, tokens: &[UniCase<&str>], i: usize) -> f64 {
+// Replaces the valences of the words of sentiment laden idioms, i.e. "under the weather", with
+// the valence of the idiom. The last word of an idiom carries its valence and the other words are
+// neutral. A negation right before an idiom negates it.
+fn idioms_check(
+    tokens: &[UniCase<&str>],
+    sentiments: &mut [f64],
+    negations: &dyn NegationDetector,
+    negation_scalar: f64,
+    mut trace: Option<&mut [Vec<Adjustment>]>,
+) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some((len, mut valence)) = SENTIMENT_LADEN_IDIOMS.longest_match(&tokens[i..]) else {
+            i += 1;
+            continue;
+        };
+        if i > 0 && negations.is_negation(tokens, i - 1) {
+            valence *= negation_scalar;
+        }
+        for j in i..i + len {
+            let after = if j == i + len - 1 { valence } else { 0f64 };
+            let token_trace = trace.as_deref_mut().map(|t| &mut t[j]);
+            record(token_trace, Rule::Idiom, sentiments[j], after);
+            sentiments[j] = after;
+        }
+        i += len;
+    }
+}
+
+/**
+ * Replaces the valence of the word with the valence of a special case idiom in the window from
+ * three words before to two words after it, and adds the boosters in the three words before it.
+ * If several idioms match, the idiom with the most words wins, and idioms with the same number of
+ * words are tried in alphabetical order.
+ **/
+fn special_idioms_check(
+    valence: f64,
+    tokens: &[UniCase<&str>],
+    i: usize,
+    special_idioms: &PhraseTable,
+    boosters: &PhraseTable,
+    matching: IdiomMatching,
+) -> f64 {
     debug_assert!(i > 2);
     let mut valence = valence;
     let mut end_i = i + 1;
@@ -248,60 +1138,183 @@ fn special_idioms_check(valence: f64, tokens: &[UniCase<&str>], i: usize) -> f64
         end_i = min(i + 3, tokens.len());
     }
 
-    if tokens.iter().any(|t| SPECIAL_CASE_EARLY_RETURN.contains(t)) {
-        let target_window = tokens[(i - 3)..end_i]
-            .iter()
-            .map(std::convert::AsRef::as_ref)
-            .collect::<Vec<&str>>()
-            .join(" ")
-            .to_lowercase();
-
-        for (key, val) in SPECIAL_CASE_IDIOMS.iter() {
-            if target_window.contains(key.as_ref()) {
-                valence = *val;
-                break;
+    if tokens.iter().any(|t| special_idioms.contains_word(t)) {
+        let idiom = match matching {
+            IdiomMatching::Tokens => special_idioms.first_within(&tokens[(i - 3)..end_i]),
+            IdiomMatching::Substring => {
+                let target_window = join_lowercase(&tokens[(i - 3)..end_i]);
+                special_idioms
+                    .iter()
+                    .find(|(key, _)| target_window.contains(key))
+                    .map(|(_, val)| val)
             }
+        };
+        if let Some(idiom) = idiom {
+            valence = idiom;
         }
     }
 
-    if tokens.iter().any(|t| BOOSTER_DICT_EARLY_RETURN.contains(t)) {
-        let prev_three = tokens[(i - 3)..i]
-            .iter()
-            .map(std::convert::AsRef::as_ref)
-            .collect::<Vec<&str>>()
-            .join(" ")
-            .to_lowercase();
-        for (key, val) in BOOSTER_DICT.iter() {
-            // println!("{prev_three}");
-            // println!("{key}");
-            if prev_three.contains(key.as_ref()) {
-                valence += *val;
+    // Boosters before the word, including phrases like "sort of", are already applied by
+    // `scalar_inc_dec`. Earlier versions added single words a second time.
+    if matching == IdiomMatching::Substring && tokens.iter().any(|t| boosters.contains_word(t)) {
+        let prev_three = join_lowercase(&tokens[(i - 3)..i]);
+        for (key, val) in boosters.iter().filter(|(key, _)| !key.contains(' ')) {
+            if prev_three.contains(key) {
+                valence += val;
             }
         }
     }
     valence
 }
 
+fn join_lowercase(tokens: &[UniCase<&str>]) -> String {
+    tokens
+        .iter()
+        .map(std::convert::AsRef::as_ref)
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::negation::Negations;
+    use crate::preprocess::{Normalizer, Redaction};
+    use crate::static_resources::EMOTICONS;
 
     #[test]
     fn but_check_test() {
-        let tokens: Vec<UniCase<&str>> = [
-            "yeah", "waffles", "are", "great", "but", "have", "you", "ever", "tried", "spam",
-        ]
-        .iter()
-        .map(|r| UniCase::new(*r))
-        .collect();
+        let parsed = ParsedText::from_text(
+            "yeah waffles are great but have you ever tried spam",
+            &DefaultTokenizer::new(),
+            &ScoringConfig::default(),
+        );
         let mut sents = vec![0.5, 0.1, 0.0, 0.2, 0.6, 0.25, 0.5, 0.5, 0.5, 0.5];
-        but_check(&tokens, &mut sents);
+        contrast_check(&parsed, &mut sents, &CONTRASTIVE_MARKERS, &EMOTICONS, None);
         assert_eq!(
             sents,
             vec![0.25, 0.05, 0.0, 0.1, 0.6, 0.375, 0.75, 0.75, 0.75, 0.75]
         );
     }
 
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn contrast_check_test() {
+        let around = |before, after| Contrast::Around { before, after };
+        let markers: PhraseTable<Contrast> = [
+            ("but", around(0.5, 1.5)),
+            ("however", around(0.25, 2.0)),
+            ("even so", around(0.5, 1.0)),
+            (
+                "although",
+                Contrast::Clause {
+                    clause: 0.5,
+                    rest: 2.0,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let contrast = |text| {
+            let parsed =
+                ParsedText::from_text(text, &DefaultTokenizer::new(), &ScoringConfig::default());
+            let mut sents = vec![1.0; parsed.tokens.len()];
+            contrast_check(&parsed, &mut sents, &markers, &EMOTICONS, None);
+            sents
+        };
+        assert_eq!(
+            contrast("great screen but bad battery. shipping fast but packaging awful"),
+            vec![0.5, 0.5, 1.0, 1.5, 1.5, 0.5, 0.5, 1.0, 1.5, 1.5]
+        );
+        assert_eq!(
+            contrast("nice but slow, however cheap"),
+            vec![0.125, 0.25, 0.375, 1.5, 3.0]
+        );
+        assert_eq!(
+            contrast("too slow. But cheap! Even so, meh"),
+            vec![0.25, 0.25, 0.5, 0.75, 1.5, 1.5, 1.5]
+        );
+        // The clause the marker introduces ends at the comma
+        assert_eq!(
+            contrast("although slow to arrive, works great. fine although"),
+            vec![1.0, 0.5, 0.5, 0.5, 2.0, 2.0, 1.0, 1.0]
+        );
+        assert_eq!(
+            contrast("works great although slow; cheap"),
+            vec![2.0, 2.0, 1.0, 0.5, 2.0]
+        );
+        // Markers that end a sentence, and clause markers at the start of one, only affect their
+        // own sentence
+        assert_eq!(
+            contrast("great but. Although slow, fine"),
+            vec![1.0, 1.0, 1.0, 0.5, 2.0]
+        );
+
+        let default = SentimentIntensityAnalyzer::new();
+        let compound =
+            |analyzer: &SentimentIntensityAnalyzer, text| analyzer.polarity_scores(text).compound;
+        for text in [
+            "The screen is great, however the battery is awful",
+            "Although the screen is great, the battery is awful",
+            "The battery is awful, even though the screen is great",
+        ] {
+            assert!(compound(&default, text) < 0.0, "{text}");
+        }
+        assert_eq!(
+            compound(&default, "I like it, however"),
+            compound(&default, "I like it")
+        );
+        // "yet" is mostly an adverb, and no marker unless it is added
+        assert_eq!(
+            compound(&default, "not yet good"),
+            compound(&default, "not good")
+        );
+        assert_eq!(
+            compound(&default, "yet another great product"),
+            compound(&default, "another great product")
+        );
+
+        let text = "The screen is great, on the other hand the battery is awful";
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .contrastive_marker("on the other hand", 0.5, 1.5)
+            .contrastive_marker("yet", 0.5, 1.5)
+            .concessive_marker("whereas", 0.5, 1.5)
+            .remove_contrastive_marker("but")
+            .build();
+        assert!(compound(&analyzer, text) < compound(&default, text));
+        assert!(compound(&analyzer, "The screen is great, yet the battery is awful") < 0.0);
+        assert!(compound(&analyzer, "whereas the screen is great, it is awful") < 0.0);
+        assert_eq!(
+            compound(&analyzer, "good but bad"),
+            compound(&default, "good bad")
+        );
+        let explanation = default.explain("Although good, it is bad");
+        assert_eq!(
+            explanation.tokens[1].adjustments[0].rule,
+            Rule::Contrast { marker: 0 }
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn negation_check_test() {
+        let check = |text: &str| {
+            let tokens: Vec<UniCase<&str>> = text.split_whitespace().map(UniCase::new).collect();
+            negation_check(1.0, &tokens, 2, 3, &*NEGATIONS, -0.74)
+        };
+        // "never" followed by "so" or "this"
+        assert_eq!(check("never so very good"), 1.25);
+        assert_eq!(check("never been this good"), 1.25);
+        assert_eq!(check("it is so good"), 1.0);
+        assert_eq!(check("not that this good"), -0.74);
+        // "without" followed by "doubt"
+        assert_eq!(check("without doubt very good"), 1.0);
+        assert_eq!(check("without any doubt good"), 1.0);
+        assert_eq!(check("never any doubt good"), -0.74);
+        assert_eq!(check("not my doubt good"), -0.74);
+    }
+
     #[test]
     fn embedded_emoji_test() {
         let analyzer = SentimentIntensityAnalyzer::new();
@@ -321,4 +1334,686 @@ mod tests {
             "woah there grinning face grinning face grinning face :) :)"
         );
     }
+
+    #[test]
+    fn emoji_sequence_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        assert_eq!(
+            analyzer.append_emoji_descriptions("proud 👨🏿‍🎓!"),
+            "proud man student: dark skin tone!"
+        );
+        assert_eq!(
+            analyzer.append_emoji_descriptions("👍🏽🇳🇱 1️⃣ 👨‍👩‍👧"),
+            "thumbs up: medium skin tone Netherlands keycap: 1 family: man, woman, girl"
+        );
+
+        // Sequences that are not in the lexicon fall back to the emoji they start with
+        let emoji_lexicon: EmojiLexicon = [("👍", "thumbs up"), ("🏽", "medium skin tone")]
+            .into_iter()
+            .collect();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .emoji_lexicon(&emoji_lexicon)
+            .build();
+        assert_eq!(
+            analyzer.append_emoji_descriptions("nice👍🏽"),
+            "nice thumbs up medium skin tone"
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn emoji_valences_test() {
+        let emoji_valences: Lexicon = [("😂", 2.5), ("👍🏽", 1.5)].into_iter().collect();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .emoji_valences(&emoji_valences)
+            .build();
+        assert_eq!(
+            analyzer.append_emoji_descriptions("lol😂😂 👍🏽👍"),
+            "lol 😂 😂 👍🏽 thumbs up"
+        );
+        assert_eq!(
+            analyzer.polarity_scores("😂").compound,
+            normalize_score(2.5, ScoringConfig::default().normalization_alpha)
+        );
+        assert!(
+            analyzer.polarity_scores("very 😂").compound > analyzer.polarity_scores("😂").compound
+        );
+        assert!(analyzer.polarity_scores("not 😂").compound < 0.0);
+
+        // Emoji are not treated as all caps words
+        let explanation = analyzer.explain("GREAT day 😂");
+        assert_eq!(explanation.tokens[2].token, "😂");
+        assert_eq!(explanation.tokens[2].lexicon_valence, Some(2.5));
+        assert_eq!(explanation.tokens[2].valence, 2.5);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn custom_lexicon_test() {
+        let lexicon: Lexicon = [("bullish", 2.0), ("^_^", 1.0)].into_iter().collect();
+        let emoji_lexicon: EmojiLexicon = [("📈", "bullish")].into_iter().collect();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .lexicon(&lexicon)
+            .emoji_lexicon(&emoji_lexicon)
+            .build();
+        assert!(analyzer.polarity_scores("so bullish 📈").compound > 0.0);
+        assert_eq!(analyzer.polarity_scores("good").compound, 0.0);
+        assert!(analyzer.polarity_scores("ok^_^").compound > 0.0);
+        assert_eq!(analyzer.polarity_scores("ok:)").compound, 0.0);
+        drop(analyzer);
+
+        let owned: SentimentIntensityAnalyzer<'static> = SentimentIntensityAnalyzer::builder()
+            .lexicon(lexicon.into_owned())
+            .build();
+        let shared = std::sync::Arc::new(owned);
+        let handle = std::thread::spawn(move || shared.polarity_scores("bullish").compound);
+        assert!(handle.join().unwrap() > 0.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn overlay_test() {
+        let mut gaming = LexiconOverlay::new();
+        gaming.set("sick", 2.0).set("gg", 1.5).set("\\o/", 2.0);
+        let mut polite = LexiconOverlay::new();
+        polite.suppress("gg");
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .overlay(&gaming)
+            .overlay(&polite)
+            .build();
+        assert!(analyzer.polarity_scores("that combo was sick").compound > 0.0);
+        assert_eq!(analyzer.polarity_scores("gg").compound, 0.0);
+        assert_eq!(
+            analyzer.polarity_scores("good game").compound,
+            SentimentIntensityAnalyzer::new()
+                .polarity_scores("good game")
+                .compound
+        );
+        // Emoticons of overlays and emoji valences are split from words like those of the lexicon
+        assert_eq!(
+            analyzer.polarity_scores("won\\o/").compound,
+            analyzer.polarity_scores("won \\o/").compound
+        );
+        assert!(analyzer.polarity_scores("won\\o/").compound > 0.5);
+        let emoji_valences: Lexicon = [("(y)", 1.5)].into_iter().collect();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .emoji_valences(emoji_valences)
+            .build();
+        assert!(
+            analyzer.polarity_scores("thanks(y)").compound
+                > analyzer.polarity_scores("thanks").compound
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn explain_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let text = "The food is not GOOD, but the service was great!";
+        let explanation = analyzer.explain(text);
+        let scores = analyzer.polarity_scores(text);
+        assert_eq!(explanation.scores.compound, scores.compound);
+        assert_eq!(explanation.punctuation_amplifier, 0.292);
+
+        let good = &explanation.tokens[4];
+        assert_eq!(good.token, "GOOD");
+        assert_eq!(good.lexicon_valence, Some(1.9));
+        let rules: Vec<Rule> = good.adjustments.iter().map(|a| a.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                Rule::AllCaps,
+                Rule::Negation { distance: 1 },
+                Rule::Contrast { marker: 5 }
+            ]
+        );
+        assert_eq!(good.adjustments[0].before, 1.9);
+        assert_eq!(good.adjustments.last().unwrap().after, good.valence);
+
+        let great = &explanation.tokens[9];
+        assert_eq!(great.valence, great.lexicon_valence.unwrap() * 1.5);
+    }
+
+    #[test]
+    fn token_spans_test() {
+        fn spans<'t>(
+            analyzer: &SentimentIntensityAnalyzer,
+            text: &'t str,
+        ) -> Vec<(String, &'t str)> {
+            let explanation = analyzer.explain(text);
+            explanation
+                .tokens
+                .into_iter()
+                .map(|t| (t.token, &text[t.span]))
+                .collect()
+        }
+
+        let analyzer = SentimentIntensityAnalyzer::new();
+        assert_eq!(
+            spans(&analyzer, "Très bien,  \"nice\"!😀ok"),
+            vec![
+                ("Très".to_string(), "Très"),
+                ("bien".to_string(), "bien"),
+                ("nice".to_string(), "nice"),
+                ("grinning".to_string(), "😀"),
+                ("face".to_string(), "😀"),
+                ("ok".to_string(), "ok"),
+            ]
+        );
+
+        let emoji_valences: Lexicon = [("👨🏿‍🎓", 2.0)].into_iter().collect();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .emoji_valences(emoji_valences)
+            .build();
+        assert_eq!(
+            spans(&analyzer, "yay👨🏿‍🎓🇳🇱ok"),
+            vec![
+                ("yay".to_string(), "yay"),
+                ("👨🏿‍🎓".to_string(), "👨🏿‍🎓"),
+                ("Netherlands".to_string(), "🇳🇱"),
+                ("ok".to_string(), "ok"),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn idioms_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        assert!(analyzer.polarity_scores("I am under the weather").compound < 0.0);
+        assert!(
+            analyzer
+                .polarity_scores("I am not under the weather")
+                .compound
+                > 0.0
+        );
+        assert!(
+            analyzer
+                .polarity_scores("we are in the black, finally")
+                .compound
+                > 0.0
+        );
+        // Only whole tokens match
+        assert_eq!(analyzer.polarity_scores("in the reddit").compound, 0.0);
+
+        let explanation = analyzer.explain("Break a leg, have fun");
+        // "a" is not a token
+        let idiom: Vec<f64> = explanation.tokens[..2].iter().map(|t| t.valence).collect();
+        assert_eq!(idiom, vec![0.0, 2.0]);
+        assert_eq!(explanation.tokens[1].adjustments[0].rule, Rule::Idiom);
+        assert!(explanation.tokens[3].valence > 0.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn special_idioms_test() {
+        let default = SentimentIntensityAnalyzer::new();
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .special_idiom("could be worse", 1.5)
+            .special_idiom(String::from("The Bomb"), 1.0)
+            .remove_special_idiom("yeah right")
+            .build();
+        let text = "well the food here could be worse";
+        assert!(default.polarity_scores(text).compound < 0.0);
+        assert!(analyzer.polarity_scores(text).compound > 0.0);
+
+        let text = "the food was great, yeah right";
+        assert!(default.polarity_scores(text).compound < analyzer.polarity_scores(text).compound);
+        assert_eq!(
+            analyzer.polarity_scores(text).compound,
+            SentimentIntensityAnalyzer::builder()
+                .remove_special_idiom("yeah right")
+                .build()
+                .polarity_scores(text)
+                .compound
+        );
+
+        let text = "this new album is the bomb";
+        let bomb = analyzer.explain(text).tokens[5].valence;
+        assert_eq!(bomb, 1.0);
+        assert_eq!(default.explain(text).tokens[5].valence, 3.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn idiom_priority_test() {
+        let text = "this album is the bomb";
+        let bomb = |analyzer: SentimentIntensityAnalyzer| analyzer.explain(text).tokens[4].valence;
+        let longest = SentimentIntensityAnalyzer::builder()
+            .special_idiom("is the bomb", 1.0)
+            .build();
+        assert_eq!(bomb(longest), 1.0);
+        // "album is" and "the bomb" both match and have two words
+        let alphabetical = SentimentIntensityAnalyzer::builder()
+            .special_idiom("album is", 0.5)
+            .build();
+        assert_eq!(bomb(alphabetical), 0.5);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn idiom_matching_test() {
+        let tokens = SentimentIntensityAnalyzer::new();
+        let substring = SentimentIntensityAnalyzer::builder()
+            .idiom_matching(IdiomMatching::Substring)
+            .build();
+        let valence =
+            |analyzer: &SentimentIntensityAnalyzer, text| analyzer.explain(text).tokens[3].valence;
+        // "so" in "also", "more" in "moreover" and "less" in "unless"
+        for text in [
+            "it was also good and so fast",
+            "moreover the food good, more please",
+            "unless it was good, less is more",
+        ] {
+            assert_eq!(valence(&tokens, text), 1.9);
+            assert_ne!(valence(&substring, text), 1.9);
+        }
+        // "the shit" is not in "the shitty"
+        assert!(tokens.polarity_scores("the shitty food was awful").compound < 0.0);
+        // Boosters count once, and a second time like in earlier versions
+        assert_eq!(valence(&tokens, "it was very good"), 1.9 + B_INCR);
+        assert_eq!(
+            valence(&substring, "it was very good"),
+            1.9 + B_INCR + B_INCR
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn elongation_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let emphasize = SentimentIntensityAnalyzer::builder()
+            .elongation(Elongation::Emphasize)
+            .build();
+        let ignore = SentimentIntensityAnalyzer::builder()
+            .elongation(Elongation::Ignore)
+            .build();
+        let compound =
+            |analyzer: &SentimentIntensityAnalyzer, text| analyzer.polarity_scores(text).compound;
+        assert_eq!(
+            compound(&analyzer, "soooo goooood"),
+            compound(&analyzer, "so good")
+        );
+        assert_eq!(
+            compound(&analyzer, "I haaaate it"),
+            compound(&analyzer, "I hate it")
+        );
+        assert_eq!(
+            compound(&analyzer, "it's nooot GOOOOD!"),
+            compound(&analyzer, "it's not GOOD!")
+        );
+        assert!(compound(&emphasize, "goooood") > compound(&analyzer, "goooood"));
+        assert!(compound(&emphasize, "haaaate") < compound(&analyzer, "haaaate"));
+        assert_eq!(compound(&ignore, "soooo goooood"), 0.0);
+        // Known words and words that do not elongate a known word are kept
+        assert_eq!(
+            compound(&emphasize, "zzz good"),
+            compound(&analyzer, "zzz good")
+        );
+
+        let text = "I loooove it, it's sooo cuuute";
+        let explanation = emphasize.explain(text);
+        let tokens: Vec<(&str, &str)> = explanation
+            .tokens
+            .iter()
+            .map(|t| (&*t.token, &text[t.span.clone()]))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("love", "loooove"),
+                ("it", "it"),
+                ("it's", "it's"),
+                ("so", "sooo"),
+                ("cute", "cuuute"),
+            ]
+        );
+        let adjustments = &explanation.tokens[4].adjustments;
+        assert_eq!(adjustments[0].rule, Rule::Elongation);
+        assert_eq!(adjustments[1].rule, Rule::Booster { distance: 1 });
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn boosters_test() {
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .booster("mega", 0.5)
+            .booster(String::from("lowkey"), -0.2)
+            .booster("very", 0.4)
+            .remove_booster("extremely")
+            .scoring_config(ScoringConfig {
+                b_decr: -0.1,
+                ..ScoringConfig::default()
+            })
+            .build();
+        let valence = |text| analyzer.explain(text).tokens[1].valence;
+        assert_eq!(valence("mega good"), 1.9 + 0.5);
+        assert_eq!(valence("mega bad"), -2.5 - 0.5);
+        assert_eq!(valence("lowkey good"), 1.9 - 0.2);
+        assert_eq!(valence("very good"), 1.9 + 0.4);
+        assert_eq!(valence("barely good"), 1.9 - 0.1);
+        assert_eq!(valence("extremely good"), 1.9);
+        // A booster carries no sentiment of its own
+        assert_eq!(analyzer.explain("mega good").tokens[0].valence, 0.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn negation_detector_test() {
+        struct Scots;
+        impl NegationDetector for Scots {
+            fn is_negation(&self, tokens: &[UniCase<&str>], i: usize) -> bool {
+                tokens[i] == UniCase::new("nae")
+            }
+        }
+        let default = SentimentIntensityAnalyzer::new();
+        let mut negations = Negations::builtin();
+        negations
+            .insert("no way")
+            .insert("aint nobody")
+            .set_contractions(false);
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .negation_detector(&negations)
+            .build();
+        assert!(default.polarity_scores("no way that is good").compound > 0.0);
+        assert!(analyzer.polarity_scores("no way that is good").compound < 0.0);
+        assert!(analyzer.polarity_scores("aint nobody happy").compound < 0.0);
+        assert!(default.polarity_scores("mayn't you be happy").compound < 0.0);
+        assert!(analyzer.polarity_scores("mayn't you be happy").compound > 0.0);
+        drop(analyzer);
+
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .negation_detector(Scots)
+            .build();
+        assert!(analyzer.polarity_scores("nae good").compound < 0.0);
+        assert!(analyzer.polarity_scores("not good").compound > 0.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn tokenizer_test() {
+        // Text that was tokenized before, with tokens separated by '|'
+        struct Pretokenized;
+        impl Tokenizer for Pretokenized {
+            fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+                let mut start = 0;
+                text.split('|')
+                    .map(|token| {
+                        let span = start..start + token.len();
+                        start = span.end + 1;
+                        span
+                    })
+                    .collect()
+            }
+        }
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .tokenizer(Pretokenized)
+            .build();
+        let explanation = analyzer.explain("not|very good|:)");
+        let tokens: Vec<&str> = explanation.tokens.iter().map(|t| &*t.token).collect();
+        assert_eq!(tokens, vec!["not", "very good", ":)"]);
+        assert_eq!(explanation.tokens[1].span, 4..13);
+        assert_eq!(
+            analyzer.polarity_scores("great|:)").compound,
+            SentimentIntensityAnalyzer::new()
+                .polarity_scores("great :)")
+                .compound
+        );
+
+        // The default tokenizer splits emoticons of a custom lexicon from words
+        let lexicon: Lexicon = [("^_^", 1.0)].into_iter().collect();
+        let tokenizer = DefaultTokenizer::with_lexicon(&lexicon);
+        assert_eq!(tokenizer.tokenize("ok^_^"), vec![0..2, 2..5]);
+        assert_eq!(DefaultTokenizer::new().tokenize("ok^_^"), vec![0..2]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn whitespace_tokenizer_test() {
+        // Single byte tokens are dropped, so phrases like "break a leg" still match
+        struct Whitespace;
+        impl Tokenizer for Whitespace {
+            fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+                text.split_whitespace()
+                    .map(|word| {
+                        let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                        start..start + word.len()
+                    })
+                    .collect()
+            }
+        }
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .tokenizer(Whitespace)
+            .build();
+        let default = SentimentIntensityAnalyzer::new();
+        for text in [
+            "you will break a leg",
+            "it is a kind of good",
+            "a not bad movie",
+        ] {
+            assert_eq!(
+                analyzer.polarity_scores(text).compound,
+                default.polarity_scores(text).compound,
+                "{text}"
+            );
+        }
+        assert!(analyzer.polarity_scores("break a leg").compound > 0.0);
+        let explanation = analyzer.explain("break a leg");
+        let tokens: Vec<&str> = explanation.tokens.iter().map(|t| &*t.token).collect();
+        assert_eq!(tokens, vec!["break", "leg"]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn invalid_tokens_test() {
+        // Ranges that can't be sliced from the text or are out of order are ignored
+        struct Invalid;
+        impl Tokenizer for Invalid {
+            #[allow(clippy::reversed_empty_ranges)]
+            fn tokenize(&self, _text: &str) -> Vec<Range<usize>> {
+                vec![0..100, 0..4, 5..9, 7..5, 5..10, 0..4]
+            }
+        }
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .tokenizer(Invalid)
+            .build();
+        let explanation = analyzer.explain("good café");
+        let tokens: Vec<(&str, Range<usize>)> = explanation
+            .tokens
+            .iter()
+            .map(|t| (&*t.token, t.span.clone()))
+            .collect();
+        assert_eq!(tokens, vec![("good", 0..4), ("café", 5..10)]);
+        assert_eq!(
+            analyzer.polarity_scores("good café").compound,
+            SentimentIntensityAnalyzer::new()
+                .polarity_scores("good café")
+                .compound
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn preprocessors_test() {
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .preprocessor(Normalizer::Markdown)
+            .preprocessor(Normalizer::Emails(Redaction::Strip))
+            .preprocessor(Normalizer::Mentions(Redaction::Strip))
+            .preprocessor(Normalizer::Urls(Redaction::Mask))
+            .build();
+        let text = "@bob **I** <3 it😀 https://x.com/kill, mail me@x.com";
+        let explanation = analyzer.explain(text);
+        let spans: Vec<(&str, &str)> = explanation
+            .tokens
+            .iter()
+            .map(|t| (&*t.token, &text[t.span.clone()]))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("<3", "<3"),
+                ("it", "it"),
+                ("grinning", "😀"),
+                ("face", "😀"),
+                ("url", "https://x.com/kill"),
+                ("mail", "mail"),
+            ]
+        );
+        assert_eq!(
+            explanation.scores.compound,
+            SentimentIntensityAnalyzer::new()
+                .polarity_scores("I <3 it😀 url, mail")
+                .compound
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn booster_phrases_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let valence = |text| analyzer.explain(text).tokens.last().unwrap().valence;
+        assert_eq!(valence("sort of good"), valence("somewhat good"));
+        assert_eq!(valence("it was just enough good"), 1.9 + B_DECR);
+        assert_eq!(valence("it is kind of good"), valence("it is sorta good"));
+        // Phrases and single words dampen alike with tokens before them
+        for text in [
+            "it is sort of good",
+            "it is somewhat good",
+            "the food here is kind of good",
+        ] {
+            assert_eq!(valence(text), 1.9 + B_DECR);
+        }
+        assert!(analyzer
+            .explain("sort of good")
+            .tokens
+            .iter()
+            .take(2)
+            .all(|t| t.valence == 0.0));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn scoring_config_test() {
+        let default = SentimentIntensityAnalyzer::new();
+        let configured = SentimentIntensityAnalyzer::builder()
+            .scoring_config(ScoringConfig::default())
+            .build();
+        let text = "VADER is VERY SMART, uber handsome, and FRIGGIN FUNNY!!!";
+        assert_eq!(
+            default.polarity_scores(text).compound,
+            configured.polarity_scores(text).compound
+        );
+
+        let short_texts = SentimentIntensityAnalyzer::builder()
+            .scoring_config(ScoringConfig {
+                normalization_alpha: 5.0,
+                b_incr: 0.5,
+                ..ScoringConfig::default()
+            })
+            .build();
+        assert!(
+            short_texts.polarity_scores("good").compound > default.polarity_scores("good").compound
+        );
+        let very_good = short_texts.explain("very good");
+        assert_eq!(very_good.tokens[1].adjustments[0].after, 1.9 + 0.5);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn document_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let text = "The screen is great. But the battery is awful. Shipping was fast!";
+        let document = analyzer.polarity_scores_document(text);
+        assert_eq!(document.sentences.len(), 3);
+        assert_eq!(
+            &text[document.sentences[2].range.clone()],
+            "Shipping was fast!"
+        );
+        assert_eq!(
+            document.sentences[0].scores.compound,
+            analyzer.polarity_scores("The screen is great.").compound
+        );
+        let mean = document
+            .sentences
+            .iter()
+            .map(|s| s.scores.compound)
+            .sum::<f64>()
+            / 3.0;
+        assert_eq!(document.scores.compound, mean);
+
+        assert_eq!(analyzer.polarity_scores_document("").scores.compound, 0.0);
+
+        // Preprocessors remove code and scripts before the text is split into sentences
+        let analyzer = SentimentIntensityAnalyzer::builder()
+            .preprocessor(Normalizer::Html)
+            .preprocessor(Normalizer::Markdown)
+            .build();
+        let text = "Works fine. ```kill. hate``` ok. Great <script>die. kill hate()</script> app!";
+        let document = analyzer.polarity_scores_document(text);
+        let sentences: Vec<&str> = document
+            .sentences
+            .iter()
+            .map(|s| &text[s.range.clone()])
+            .collect();
+        assert_eq!(
+            sentences,
+            vec![
+                "Works fine.",
+                "ok.",
+                "Great <script>die. kill hate()</script> app!"
+            ]
+        );
+        assert!(document.sentences.iter().all(|s| s.scores.neg == 0.0));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    #[allow(clippy::float_cmp)]
+    fn batch_test() {
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let texts = ["The book was good.", "", "Today SUX!", "Not bad at all"];
+        let batch = analyzer.polarity_scores_batch(&texts);
+        assert_eq!(batch.len(), texts.len());
+        for (text, scores) in texts.iter().zip(&batch) {
+            assert_eq!(scores.compound, analyzer.polarity_scores(text).compound);
+        }
+
+        let owned: Vec<String> = texts.iter().map(ToString::to_string).collect();
+        let compounds: Vec<f64> = analyzer
+            .polarity_scores_par_iter(owned)
+            .map(|s| s.compound)
+            .collect();
+        assert_eq!(
+            compounds,
+            batch.iter().map(|s| s.compound).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_test() {
+        let scores = SentimentIntensity {
+            neg: 0.0,
+            neu: 0.5,
+            pos: 0.5,
+            compound: 0.25,
+        };
+        let json = serde_json::to_string(&scores).unwrap();
+        assert_eq!(json, r#"{"neg":0.0,"neu":0.5,"pos":0.5,"compound":0.25}"#);
+        let parsed: SentimentIntensity = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{parsed:?}"), format!("{scores:?}"));
+
+        let analyzer = SentimentIntensityAnalyzer::new();
+        let explanation = serde_json::to_value(analyzer.explain("not GOOD")).unwrap();
+        assert_eq!(
+            explanation["tokens"][1]["adjustments"][1]["rule"],
+            serde_json::json!({"kind": "negation", "distance": 1})
+        );
+        let document =
+            serde_json::to_value(analyzer.polarity_scores_document("Good. Bad.")).unwrap();
+        assert_eq!(
+            document["sentences"][1]["range"],
+            serde_json::json!({"start": 6, "end": 10})
+        );
+    }
 }
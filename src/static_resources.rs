@@ -1,13 +1,24 @@
-use hashbrown::{HashMap, HashSet};
+#![allow(clippy::non_std_lazy_statics)]
+
+use hashbrown::HashSet;
 use lazy_static::lazy_static;
 use regex::Regex;
 use unicase::UniCase;
 
+use crate::{
+    lexicon::{EmojiLexicon, Lexicon},
+    negation::Negations,
+    phrases::PhraseTable,
+    sentiment_intensity_analyzer::Contrast,
+    tokenizer::Emoticons,
+};
+
 //empirically derived constants for scaling/amplifying sentiments
 pub(crate) const B_INCR: f64 = 0.293;
 pub(crate) const B_DECR: f64 = -0.293;
 
 pub(crate) const C_INCR: f64 = 0.733;
+pub(crate) const E_INCR: f64 = 0.293;
 pub(crate) const NEGATION_SCALAR: f64 = -0.740;
 
 //sentiment increases for text with question or exclamation marks
@@ -16,17 +27,42 @@ pub(crate) const EMARK_INCR: f64 = 0.292;
 
 //Maximum amount of question or question marks before their contribution to sentiment is
 //disregarded
-pub(crate) const MAX_EMARK: i32 = 4;
-pub(crate) const MAX_QMARK: i32 = 3;
+pub(crate) const MAX_EMARK: u32 = 4;
+pub(crate) const MAX_QMARK: u32 = 3;
 pub(crate) const MAX_QMARK_INCR: f64 = 0.96;
 
 pub(crate) const NORMALIZATION_ALPHA: f64 = 15.0;
 
+//Longest elongated word, in characters, that is looked up in shortened forms
+pub(crate) const MAX_ELONGATED_LEN: usize = 64;
+
+#[rustfmt::skip]
+const BOOSTER_INCR_WORDS: &[&str] = &[
+    "absolutely", "amazingly", "awfully", "completely", "considerable", "considerably",
+    "decidedly", "deeply", "effing", "enormous", "enormously", "entirely", "especially",
+    "exceptional", "exceptionally", "extreme", "extremely", "fabulously", "flipping",
+    "flippin", "frackin", "fracking", "fricking", "frickin", "frigging", "friggin", "fully",
+    "fuckin", "fucking", "fuggin", "fugging", "greatly", "hella", "highly", "hugely",
+    "incredible", "incredibly", "intensely", "major", "majorly", "more", "most",
+    "particularly", "purely", "quite", "really", "remarkably", "so", "substantially",
+    "thoroughly", "total", "totally", "tremendous", "tremendously", "uber", "unbelievably",
+    "unusually", "utter", "utterly", "very"
+];
+
+#[rustfmt::skip]
+const BOOSTER_DECR_WORDS: &[&str] = &[
+    "almost", "barely", "hardly", "just enough", "kind of", "kinda", "kindof",
+    "kind-of", "less", "little", "marginal", "marginally", "occasional", "occasionally",
+    "partly", "scarce", "scarcely", "slight", "slightly", "somewhat", "sort of", "sorta",
+    "sortof", "sort-of"
+];
+
 pub(crate) static RAW_LEXICON: &str = include_str!("resources/vader_lexicon.txt");
 pub(crate) static RAW_EMOJI_LEXICON: &str = include_str!("resources/emoji_utf8_lexicon.txt");
 
 lazy_static! {
-    pub(crate) static ref NEGATION_TOKENS: HashSet<UniCase<&'static str>> = [
+    pub(crate) static ref NEGATIONS: Negations<'static> = {
+        let mut negations: Negations = [
         "aint", "arent", "cannot", "cant", "couldnt", "darent", "didnt", "doesnt",
         "ain't", "aren't", "can't", "couldn't", "daren't", "didn't", "doesn't",
         "dont", "hadnt", "hasnt", "havent", "isnt", "mightnt", "mustnt", "neither",
@@ -34,74 +70,70 @@ lazy_static! {
         "neednt", "needn't", "never", "none", "nope", "nor", "not", "nothing", "nowhere",
         "oughtnt", "shant", "shouldnt", "uhuh", "wasnt", "werent",
         "oughtn't", "shan't", "shouldn't", "uh-uh", "wasn't", "weren't",
-        "without", "wont", "wouldnt", "won't", "wouldn't", "rarely", "seldom", "despite"].into_iter().map(UniCase::new).collect();
-
-
-    pub(crate) static ref BOOSTER_DICT: HashMap<UniCase<&'static str>, f64> =   {
-        let mut map = HashMap::new();
-        for word in &[
-            "absolutely", "amazingly", "awfully", "completely", "considerable", "considerably",
-            "decidedly", "deeply", "effing", "enormous", "enormously", "entirely", "especially",
-            "exceptional", "exceptionally", "extreme", "extremely", "fabulously", "flipping",
-            "flippin", "frackin", "fracking", "fricking", "frickin", "frigging", "friggin", "fully",
-            "fuckin", "fucking", "fuggin", "fugging", "greatly", "hella", "highly", "hugely",
-            "incredible", "incredibly", "intensely", "major", "majorly", "more", "most",
-            "particularly", "purely", "quite", "really", "remarkably", "so", "substantially",
-            "thoroughly", "total", "totally", "tremendous", "tremendously", "uber", "unbelievably",
-            "unusually", "utter", "utterly", "very"
-        ] {
-            map.insert(UniCase::new(*word), B_INCR);
-        }
-        // Adding B_DECR entries
-        for word in &[
-            "almost", "barely", "hardly", "just enough", "kind of", "kinda", "kindof",
-            "kind-of", "less", "little", "marginal", "marginally", "occasional", "occasionally",
-            "partly", "scarce", "scarcely", "slight", "slightly", "somewhat", "sort of", "sorta",
-            "sortof", "sort-of"
-        ] {
-            map.insert(UniCase::new(*word), B_DECR);
-        }
-        map
+        "without", "wont", "wouldnt", "won't", "wouldn't", "rarely", "seldom", "despite"].into_iter().collect();
+        negations.set_contractions(true);
+        negations
     };
 
-    pub(crate) static ref BOOSTER_DICT_EARLY_RETURN: HashSet<UniCase<&'static str>> = BOOSTER_DICT.keys().flat_map(|s| s.split_whitespace()).map(UniCase::new).collect();
-    /**
-     * These dicts were used in some WIP or planned features in the original
-     * I may implement them later if I can understand how they're intended to work
-     **/
 
-    // // check for sentiment laden idioms that do not contain lexicon words (future work, not yet implemented)
-    // static ref SENTIMENT_LADEN_IDIOMS: HashMap<&'static str, f64> = hashmap![
-    //      "cut the mustard" => 2.0, "hand to mouth" => tokens.len()-2.0,
-    //      "back handed" => -2.0, "blow smoke" => -2.0, "blowing smoke" => -2.0,
-    //      "upper hand" => 1.0, "break a leg" => 2.0,
-    //      "cooking with gas" => 2.0, "in the black" => 2.0, "in the red" => -2.0,
-    //      "on the ball" => 2.0, "under the weather" => -2.0];
+    pub(crate) static ref BOOSTER_DICT: PhraseTable<'static> = booster_dict(B_INCR, B_DECR);
 
+    // sentiment laden idioms that do not contain lexicon words
+    pub(crate) static ref SENTIMENT_LADEN_IDIOMS: PhraseTable<'static> = [
+        ("cut the mustard", 2.0), ("hand to mouth", -2.0), ("back handed", -2.0),
+        ("blow smoke", -2.0), ("blowing smoke", -2.0), ("upper hand", 1.0), ("break a leg", 2.0),
+        ("cooking with gas", 2.0), ("in the black", 2.0), ("in the red", -2.0),
+        ("on the ball", 2.0), ("under the weather", -2.0)].into_iter().collect();
 
     // check for special case idioms containing lexicon words
-    pub(crate) static ref SPECIAL_CASE_IDIOMS: HashMap<UniCase<&'static str>, f64> = {
-        let mut map = HashMap::new();
-        map.insert(UniCase::new("the shit"), 3.0);
-        map.insert(UniCase::new("the bomb"), 3.0);
-        map.insert(UniCase::new("bad ass"), 1.5);
-        map.insert(UniCase::new("badass"), 1.5);
-        map.insert(UniCase::new("yeah right"), -2.0);
-        map.insert(UniCase::new("kiss of death"), -1.5);
-        map.insert(UniCase::new("to die for"), 3.0);
-        map
-    };
-    // early return if no current tokens are in the special case tokens
-    pub(crate) static ref SPECIAL_CASE_EARLY_RETURN: HashSet<UniCase<&'static str>> = SPECIAL_CASE_IDIOMS.keys().flat_map(|s| s.split_whitespace()).map(UniCase::new).collect();
+    pub(crate) static ref SPECIAL_CASE_IDIOMS: PhraseTable<'static> = [
+        ("the shit", 3.0), ("the bomb", 3.0), ("bad ass", 1.5), ("badass", 1.5),
+        ("yeah right", -2.0), ("kiss of death", -1.5), ("to die for", 3.0)].into_iter().collect();
+
+    // contrastive markers, with the scalars for the words they weaken and strengthen
+    pub(crate) static ref CONTRASTIVE_MARKERS: PhraseTable<'static, Contrast> = [
+        ("but", Contrast::Around { before: 0.5, after: 1.5 }),
+        ("however", Contrast::Around { before: 0.5, after: 1.5 }),
+        ("nevertheless", Contrast::Around { before: 0.5, after: 1.5 }),
+        ("although", Contrast::Clause { clause: 0.5, rest: 1.5 }),
+        ("though", Contrast::Clause { clause: 0.5, rest: 1.5 }),
+        ("even though", Contrast::Clause { clause: 0.5, rest: 1.5 })].into_iter().collect();
+
+    // titles, which are followed by a name and never end a sentence
+    pub(crate) static ref TITLES: HashSet<UniCase<&'static str>> = [
+        "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs"].into_iter().map(UniCase::new).collect();
+    // abbreviations that are also words or often end a sentence, i.e. "no" and "etc"
+    pub(crate) static ref ABBREVIATIONS: HashSet<UniCase<&'static str>> = [
+        "etc", "inc", "ltd", "co", "corp", "dept", "est", "approx", "fig", "no", "vol", "ca", "cf",
+        "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec"].into_iter().map(UniCase::new).collect();
 
     pub(crate) static ref ALL_CAPS_RE: Regex = Regex::new(r"^[A-Z\W]+$").unwrap();
 
+    // entities and markup removed by the built-in preprocessors
+    pub(crate) static ref URL_RE: Regex = Regex::new(r#"(?i)\b(?:[a-z][a-z0-9+.-]*://|www\.)[^\s<>"]+"#).unwrap();
+    pub(crate) static ref EMAIL_RE: Regex = Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b").unwrap();
+    pub(crate) static ref MENTION_RE: Regex = Regex::new(r"(?:^|[^\w@])(@\w+)").unwrap();
+    pub(crate) static ref HTML_TAG_RE: Regex = Regex::new(r"(?is)<!--.*?-->|<(?:script|style)\b[^>]*>.*?</(?:script|style)\s*>|</?[a-z][a-z0-9-]*(?:\s[^<>]*)?/?>").unwrap();
+    pub(crate) static ref HTML_ENTITY_RE: Regex = Regex::new(r"&(?:#([0-9]{1,7})|#[xX]([0-9a-fA-F]{1,6})|(amp|lt|gt|quot|apos|nbsp));").unwrap();
+    pub(crate) static ref MARKDOWN_CODE_RE: Regex = Regex::new(r"(?s)```.*?```|`[^`\n]+`").unwrap();
+    pub(crate) static ref MARKDOWN_BLOCK_RE: Regex = Regex::new(r"(?m)^[ \t]{0,3}(?:#{1,6}[ \t]+|(?:>[ \t]?)+|(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$)").unwrap();
+    pub(crate) static ref MARKDOWN_LINK_RE: Regex = Regex::new(r#"(!?\[)[^\]\n]*(\]\([^)\s]*(?:\s+"[^"\n]*")?\))"#).unwrap();
+    pub(crate) static ref MARKDOWN_EMPHASIS_RES: Vec<(char, Regex)> = ["**", "__", "~~", "*", "_"]
+        .into_iter()
+        .map(|marker| {
+            let c = regex::escape(&marker[..1]);
+            let m = regex::escape(marker);
+            let pattern = format!(r"({m})[^\s{c}](?:[^{c}\n]*?[^\s{c}])?({m})");
+            (marker.chars().next().unwrap(), Regex::new(&pattern).unwrap())
+        })
+        .collect();
+
     pub(crate) static ref PUNCTUATION: &'static str = "[!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~]";
 
-    pub(crate) static ref LEXICON: HashMap<UniCase<&'static str>, f64> = parse_raw_lexicon(RAW_LEXICON);
-    pub(crate) static ref EMOJI_LEXICON: HashMap<&'static str, &'static str> = parse_raw_emoji_lexicon(RAW_EMOJI_LEXICON);
+    pub(crate) static ref LEXICON: Lexicon<'static> = Lexicon::parse(RAW_LEXICON).expect("built-in lexicon is valid");
+    pub(crate) static ref EMOTICONS: Emoticons<'static> = Emoticons::from_lexicon(&LEXICON);
+    pub(crate) static ref EMOJI_LEXICON: EmojiLexicon<'static> = EmojiLexicon::parse(RAW_EMOJI_LEXICON).expect("built-in emoji lexicon is valid");
 
-    pub(crate) static ref STATIC_BUT: UniCase<&'static str> = UniCase::new("but");
     pub(crate) static ref STATIC_THIS: UniCase<&'static str> = UniCase::new("this");
     pub(crate) static ref STATIC_AT: UniCase<&'static str> = UniCase::new("at");
     pub(crate) static ref STATIC_LEAST: UniCase<&'static str> = UniCase::new("least");
@@ -110,42 +142,15 @@ lazy_static! {
     pub(crate) static ref STATIC_DOUBT: UniCase<&'static str> = UniCase::new("doubt");
     pub(crate) static ref STATIC_SO: UniCase<&'static str> = UniCase::new("so");
     pub(crate) static ref STATIC_NEVER: UniCase<&'static str> = UniCase::new("never");
-    pub(crate) static ref STATIC_KIND: UniCase<&'static str> = UniCase::new("kind");
-    pub(crate) static ref STATIC_OF: UniCase<&'static str> = UniCase::new("of");
 }
 
 /**
- * Takes the raw text of the lexicon files and creates `HashMaps`
+ * Builds the booster dictionary with the given scalars for boosters and dampeners
  **/
-pub fn parse_raw_lexicon(raw_lexicon: &str) -> HashMap<UniCase<&str>, f64> {
-    let lines = raw_lexicon.trim_end_matches('\n').split('\n');
-    let mut lex_dict = HashMap::new();
-    for line in lines {
-        if line.is_empty() {
-            continue;
-        }
-        let mut split_line = line.split('\t');
-        let word = split_line.next().unwrap();
-        let val = split_line.next().unwrap();
-        lex_dict.insert(UniCase::new(word), val.parse().unwrap());
-    }
-    lex_dict
-}
-
-pub fn parse_raw_emoji_lexicon(raw_emoji_lexicon: &str) -> HashMap<&str, &str> {
-    let lines = raw_emoji_lexicon.trim_end_matches('\n').split('\n');
-    let mut emoji_dict = HashMap::new();
-    for line in lines {
-        if line.is_empty() {
-            continue;
-        }
-        let mut split_line = line.split('\t');
-        let word = split_line.next().unwrap();
-        let desc = split_line.next().unwrap();
-
-        emoji_dict.insert(word, desc);
-    }
-    emoji_dict
+pub(crate) fn booster_dict(b_incr: f64, b_decr: f64) -> PhraseTable<'static> {
+    let incr = BOOSTER_INCR_WORDS.iter().map(|word| (*word, b_incr));
+    let decr = BOOSTER_DECR_WORDS.iter().map(|word| (*word, b_decr));
+    incr.chain(decr).collect()
 }
 
 #[cfg(test)]
@@ -155,22 +160,22 @@ mod tests {
     #[test]
     #[allow(clippy::float_cmp)]
     fn test_lexicon() {
-        assert_eq!(*LEXICON.get(&UniCase::new("feudally")).unwrap(), -0.6);
-        assert_eq!(*LEXICON.get(&UniCase::new("irrationalism")).unwrap(), -1.5);
-        assert_eq!(*LEXICON.get(&UniCase::new("sentimentalize")).unwrap(), 0.8);
-        assert_eq!(*LEXICON.get(&UniCase::new("wisewomen")).unwrap(), 1.3);
+        assert_eq!(LEXICON.get("feudally").unwrap(), -0.6);
+        assert_eq!(LEXICON.get("irrationalism").unwrap(), -1.5);
+        assert_eq!(LEXICON.get("sentimentalize").unwrap(), 0.8);
+        assert_eq!(LEXICON.get("wisewomen").unwrap(), 1.3);
     }
 
     #[test]
     fn test_emoji_lexicon() {
-        assert_eq!(EMOJI_LEXICON.get("👽").unwrap(), &"alien");
+        assert_eq!(EMOJI_LEXICON.get("👽").unwrap(), "alien");
         assert_eq!(
             EMOJI_LEXICON.get("👨🏿‍🎓").unwrap(),
-            &"man student: dark skin tone"
+            "man student: dark skin tone"
         );
         assert_eq!(
             EMOJI_LEXICON.get("🖖🏻").unwrap(),
-            &"vulcan salute: light skin tone"
+            "vulcan salute: light skin tone"
         );
     }
 }
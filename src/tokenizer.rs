@@ -0,0 +1,260 @@
+use std::{borrow::Cow, ops::Range};
+
+use hashbrown::HashSet;
+use unicase::UniCase;
+
+use crate::{
+    lexicon::{Key, Lexicon},
+    static_resources::{EMOTICONS, PUNCTUATION},
+};
+
+/// Splits text into the tokens that are scored.
+///
+/// Implement this for hashtags, code-mixed text or text that was tokenized before. Tokens are
+/// byte ranges of the text, looked up in the lexicon case-insensitively. Tokens of a single byte,
+/// like "a", are dropped like the default tokenizer drops them, so idioms, boosters and negations
+/// like "break a leg" match without them. To score words as other words, i.e. "gud" as "good",
+/// rewrite the text with a `Preprocessor` instead.
+///
+/// ```
+/// use std::ops::Range;
+///
+/// use vader_sentimental::{SentimentIntensityAnalyzer, Tokenizer};
+///
+/// // Splits "#GoodVibes" into "Good" and "Vibes"
+/// struct Hashtags;
+///
+/// impl Tokenizer for Hashtags {
+///     fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+///         let mut tokens = Vec::new();
+///         let mut start = 0;
+///         for (i, c) in text.char_indices() {
+///             if c.is_whitespace() || c == '#' || c.is_uppercase() {
+///                 if start < i {
+///                     tokens.push(start..i);
+///                 }
+///                 start = if c.is_uppercase() { i } else { i + c.len_utf8() };
+///             }
+///         }
+///         if start < text.len() {
+///             tokens.push(start..text.len());
+///         }
+///         tokens
+///     }
+/// }
+///
+/// let analyzer = SentimentIntensityAnalyzer::builder().tokenizer(Hashtags).build();
+/// assert!(analyzer.polarity_scores("#GoodVibes").compound > 0.0);
+/// ```
+pub trait Tokenizer: Send + Sync {
+    /// Splits the text into tokens, as byte ranges of the text in order. Ranges of less than two
+    /// bytes, not in the text, not on character boundaries or that start before the previous token
+    /// are ignored.
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        (**self).tokenize(text)
+    }
+}
+
+/// The tokenizer of VADER, which the analyzer uses unless it is given another one.
+///
+/// It splits text on whitespace, splits emoticons of the lexicon from the words they are attached
+/// to, and removes punctuation around words, except for emoticons like ":^)". Tokens of a single
+/// character are dropped.
+#[derive(Debug, Clone)]
+pub struct DefaultTokenizer {
+    emoticons: Cow<'static, Emoticons<'static>>,
+}
+
+impl DefaultTokenizer {
+    /// A tokenizer for the emoticons of the built-in lexicon
+    #[must_use]
+    pub fn new() -> DefaultTokenizer {
+        DefaultTokenizer {
+            emoticons: Cow::Borrowed(&*EMOTICONS),
+        }
+    }
+
+    /// A tokenizer for the emoticons of a custom lexicon, i.e. "^_^"
+    #[must_use]
+    pub fn with_lexicon(lexicon: &Lexicon) -> DefaultTokenizer {
+        DefaultTokenizer::with_words(lexicon.iter().map(|(word, _)| word))
+    }
+
+    /// A tokenizer for the emoticons among words, i.e. of a lexicon and its overlays
+    pub(crate) fn with_words<'w>(words: impl IntoIterator<Item = &'w str>) -> DefaultTokenizer {
+        DefaultTokenizer {
+            emoticons: Cow::Owned(Emoticons::from_words(words).into_owned()),
+        }
+    }
+
+    pub(crate) fn emoticons(&self) -> &Emoticons<'static> {
+        &self.emoticons
+    }
+}
+
+impl Default for DefaultTokenizer {
+    fn default() -> Self {
+        DefaultTokenizer::new()
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        let mut parts = Vec::new();
+        for chunk in text.split_whitespace() {
+            self.emoticons.split(chunk, &mut parts);
+        }
+        parts
+            .into_iter()
+            .filter(|part| part.len() > 1)
+            .map(|part| {
+                let token = strip_punc_if_word(part);
+                // Parts are slices of the text
+                let start = token.as_ptr() as usize - text.as_ptr() as usize;
+                start..start + token.len()
+            })
+            .collect()
+    }
+}
+
+// Removes punctuation from words, ie "hello!!!" -> "hello" and ",don't??" -> "don't"
+// Keeps most emoticons, ie ":^)" -> ":^)"
+fn strip_punc_if_word(token: &str) -> &str {
+    let stripped = token.trim_matches(|c| PUNCTUATION.contains(c));
+    if stripped.len() <= 1 {
+        return token;
+    }
+    stripped
+}
+
+/// Lexicon entries that contain punctuation, i.e. emoticons like ":)" and words like "j/k".
+///
+/// The tokenizer keeps these entries whole, and splits emoticons from the words they are attached
+/// to, so "great:)" becomes "great" and ":)".
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Emoticons<'a> {
+    entries: HashSet<UniCase<Cow<'a, str>>>,
+    // Length in bytes of the longest entry
+    max_len: usize,
+}
+
+impl<'a> Emoticons<'a> {
+    pub(crate) fn from_lexicon(lexicon: &'a Lexicon<'_>) -> Emoticons<'a> {
+        Emoticons::from_words(lexicon.iter().map(|(word, _)| word))
+    }
+
+    pub(crate) fn from_words(words: impl IntoIterator<Item = &'a str>) -> Emoticons<'a> {
+        let entries: HashSet<UniCase<Cow<'a, str>>> = words
+            .into_iter()
+            .filter(|word| {
+                word.len() > 1
+                    && word.contains(|c: char| c.is_ascii_punctuation())
+                    && !word.contains(char::is_whitespace)
+            })
+            .map(|word| UniCase::new(Cow::Borrowed(word)))
+            .collect();
+        let max_len = entries.iter().map(|entry| entry.len()).max().unwrap_or(0);
+        Emoticons { entries, max_len }
+    }
+
+    /// Copies all borrowed entries, so they no longer borrow from the lexicon
+    pub(crate) fn into_owned(self) -> Emoticons<'static> {
+        Emoticons {
+            entries: self
+                .entries
+                .into_iter()
+                .map(|entry| UniCase::new(Cow::Owned(entry.into_inner().into_owned())))
+                .collect(),
+            max_len: self.max_len,
+        }
+    }
+
+    pub(crate) fn contains(&self, word: &str) -> bool {
+        word.len() <= self.max_len && self.entries.contains(&Key(UniCase::new(word)))
+    }
+
+    /// Splits a whitespace separated chunk of text into the emoticons at its start and end and
+    /// the word between them, i.e. "lol:(" into "lol" and ":(". Entries of the lexicon, like
+    /// ":^)", are never split.
+    pub(crate) fn split<'t>(&self, chunk: &'t str, parts: &mut Vec<&'t str>) {
+        if !chunk.contains(|c: char| c.is_ascii_punctuation()) || self.contains(chunk) {
+            parts.push(chunk);
+        } else if let Some(end) = self.emoticon_prefix(chunk) {
+            parts.push(&chunk[..end]);
+            self.split(&chunk[end..], parts);
+        } else if let Some(emoticon) = self.emoticon_suffix(chunk) {
+            // Punctuation before the emoticon, i.e. in "!!:D", is dropped
+            if chunk[..emoticon.start].contains(char::is_alphanumeric) {
+                self.split(&chunk[..emoticon.start], parts);
+            }
+            parts.push(&chunk[emoticon]);
+        } else {
+            parts.push(chunk);
+        }
+    }
+
+    // Finds the longest emoticon at the start of the chunk that is followed by a word, and returns
+    // its end. The emoticon has to end with punctuation, so words are not cut in two.
+    fn emoticon_prefix(&self, chunk: &str) -> Option<usize> {
+        chunk
+            .char_indices()
+            .map(|(i, _)| i)
+            .take_while(|&end| end <= self.max_len)
+            .filter(|&end| chunk[..end].ends_with(|c: char| c.is_ascii_punctuation()))
+            .filter(|&end| chunk[end..].contains(char::is_alphanumeric))
+            .filter(|&end| self.contains(&chunk[..end]))
+            .last()
+    }
+
+    // Finds the longest emoticon at the end of the chunk, or before the punctuation that ends a
+    // sentence, i.e. in "great:)!". The emoticon has to start with punctuation, so "said:" is not
+    // split into "sai" and "d:".
+    fn emoticon_suffix(&self, chunk: &str) -> Option<Range<usize>> {
+        let trimmed = chunk.trim_end_matches(['.', ',', '!', '?']);
+        [chunk, trimmed].into_iter().find_map(|chunk| {
+            chunk
+                .char_indices()
+                .filter(|&(start, c)| {
+                    c.is_ascii_punctuation() && chunk.len() - start <= self.max_len
+                })
+                .map(|(start, _)| start)
+                .find(|&start| self.contains(&chunk[start..]))
+                .map(|start| start..chunk.len())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attached_emoticons() {
+        let tokenizer = DefaultTokenizer::new();
+        let tokenize = |text: &'static str| -> Vec<&str> {
+            tokenizer
+                .tokenize(text)
+                .into_iter()
+                .map(|range| &text[range])
+                .collect()
+        };
+        assert_eq!(
+            tokenize("great:) lol:( thanks!!:D"),
+            tokenize("great :) lol :( thanks :D")
+        );
+        assert_eq!(
+            tokenize(":)great:-), :^) <3, i<3"),
+            tokenize(":) great :-) :^) <3 <3")
+        );
+        assert_eq!(tokenize("!!:D"), tokenize(":D"));
+        // Lexicon entries and words that end in letters of an emoticon are kept whole
+        assert_eq!(
+            tokenize("sweet<3 j/k said: 12:30"),
+            tokenize("sweet<3 j/k said 12:30")
+        );
+    }
+}
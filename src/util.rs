@@ -1,7 +1,10 @@
+use std::ops::Range;
+
 use unicase::UniCase;
 
-use crate::static_resources::{
-    ALL_CAPS_RE, BOOSTER_DICT, C_INCR, NEGATION_TOKENS, NORMALIZATION_ALPHA,
+use crate::{
+    phrases::PhraseTable,
+    static_resources::{ALL_CAPS_RE, MAX_ELONGATED_LEN},
 };
 
 //Checks if all letters in token are capitalized
@@ -10,17 +13,9 @@ pub(crate) fn is_all_caps<S: AsRef<str>>(token: S) -> bool {
     ALL_CAPS_RE.is_match(token_ref) && token_ref.len() > 1
 }
 
-//Checks if token is in the list of NEGATION_SCALAR
-pub(crate) fn is_negated(token: &UniCase<&str>) -> bool {
-    if NEGATION_TOKENS.contains(token) {
-        return true;
-    }
-    token.contains("n't")
-}
-
 //Normalizes score between -1.0 and 1.0. Alpha value is expected upper limit for a score
-pub(crate) fn normalize_score(score: f64) -> f64 {
-    let norm_score = score / (score * score + NORMALIZATION_ALPHA).sqrt();
+pub(crate) fn normalize_score(score: f64, alpha: f64) -> f64 {
+    let norm_score = score / (score * score + alpha).sqrt();
     if norm_score < -1.0 {
         return -1.0;
     } else if norm_score > 1.0 {
@@ -29,28 +24,38 @@ pub(crate) fn normalize_score(score: f64) -> f64 {
     norm_score
 }
 
-//Checks how previous tokens affect the valence of the current token
-pub(crate) fn scalar_inc_dec(token: &UniCase<&str>, valence: f64, has_mixed_caps: bool) -> f64 {
+//Checks how the token at `j`, or the booster phrase ending at it, affects the valence of a later token
+pub(crate) fn scalar_inc_dec(
+    tokens: &[UniCase<&str>],
+    j: usize,
+    valence: f64,
+    has_mixed_caps: bool,
+    boosters: &PhraseTable,
+    c_incr: f64,
+) -> f64 {
     let mut scalar = 0.0;
-    if BOOSTER_DICT.contains_key(token) {
-        scalar = *BOOSTER_DICT.get(token).unwrap();
+    if let Some((range, booster)) = boosters.match_around(tokens, j) {
+        if range.end - 1 != j {
+            return scalar;
+        }
+        scalar = booster;
         if valence < 0.0 {
             scalar *= -1.0;
         }
-        if is_all_caps(token) && has_mixed_caps {
+        if is_all_caps(tokens[j]) && has_mixed_caps {
             if valence > 0.0 {
-                scalar += C_INCR;
+                scalar += c_incr;
             } else {
-                scalar -= C_INCR;
+                scalar -= c_incr;
             }
         }
     }
     scalar
 }
 
-pub(crate) fn sum_sentiment_scores(scores: Vec<f64>) -> (f64, f64, u32) {
+pub(crate) fn sum_sentiment_scores(scores: &[f64]) -> (f64, f64, u32) {
     let (mut pos_sum, mut neg_sum, mut neu_count) = (0f64, 0f64, 0);
-    for score in scores {
+    for &score in scores {
         if score > 0f64 {
             pos_sum += score + 1.0;
         } else if score < 0f64 {
@@ -61,3 +66,88 @@ pub(crate) fn sum_sentiment_scores(scores: Vec<f64>) -> (f64, f64, u32) {
     }
     (pos_sum, neg_sum, neu_count)
 }
+
+// Checks if text repeats a letter at least three times, i.e. "soooo"
+pub(crate) fn has_elongation(text: &str) -> bool {
+    let mut chars = text.chars().map(|c| c.to_lowercase().next().unwrap_or(c));
+    let (mut prev, mut count) = (' ', 0);
+    chars.any(|c| {
+        count = if c == prev { count + 1 } else { 1 };
+        prev = c;
+        count >= 3 && c.is_alphabetic()
+    })
+}
+
+// Finds the words of text that repeat a letter at least three times, as byte ranges. Words may
+// contain apostrophes, i.e. "dooon't".
+pub(crate) fn elongated_words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| has_elongation(word))
+        .map(move |word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            start..start + word.len()
+        })
+}
+
+// Lists the words an elongated word might stand for, with every run of three or more of the same
+// letter shortened to two or one letters, i.e. "goooood" -> "good", "god". Forms that keep two
+// letters come first. Words longer than `MAX_ELONGATED_LEN` have no forms.
+pub(crate) fn unelongated_forms(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    if chars.len() > MAX_ELONGATED_LEN {
+        return Vec::new();
+    }
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=chars.len() {
+        let same = i < chars.len() && chars[i].to_lowercase().eq(chars[start].to_lowercase());
+        if !same {
+            if i - start >= 3 {
+                runs.push(start..i);
+            }
+            start = i;
+        }
+    }
+    let shorten = |keep: &dyn Fn(usize) -> usize| {
+        let mut form = String::with_capacity(word.len());
+        let mut next = 0;
+        for (i, run) in runs.iter().enumerate() {
+            form.extend(&chars[next..run.start]);
+            form.extend(&chars[run.start..run.start + keep(i)]);
+            next = run.end;
+        }
+        form.extend(&chars[next..]);
+        form
+    };
+    // Every combination for a few runs, otherwise only all runs shortened alike
+    if runs.len() <= 4 {
+        (0..1_u32 << runs.len())
+            .map(|mask| shorten(&|i| if mask >> i & 1 == 0 { 2 } else { 1 }))
+            .collect()
+    } else {
+        vec![shorten(&|_| 2), shorten(&|_| 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unelongated_forms() {
+        assert_eq!(unelongated_forms("gooooood"), vec!["good", "god"]);
+        assert_eq!(
+            unelongated_forms("yeeesss"),
+            vec!["yeess", "yess", "yees", "yes"]
+        );
+
+        // Too many runs to combine
+        let word = "aaabbb".repeat(5);
+        assert_eq!(
+            unelongated_forms(&word),
+            vec!["aabb".repeat(5), "ab".repeat(5)]
+        );
+        assert!(unelongated_forms(&"aaabbb".repeat(20)).is_empty());
+    }
+}
@@ -0,0 +1,16 @@
+use vader_sentimental::SentimentIntensityAnalyzer;
+
+#[test]
+fn test_least() {
+    let analyzer = SentimentIntensityAnalyzer::new();
+    let fixture = include_str!("fixtures/least.tsv");
+    for line in fixture.lines().filter(|l| !l.starts_with('#')) {
+        let (sentence, expected) = line.split_once('\t').unwrap();
+        let expected: f64 = expected.parse().unwrap();
+        let compound = analyzer.polarity_scores(sentence).compound;
+        assert!(
+            (compound - expected).abs() < 0.00005,
+            "{sentence}: expected {expected}, got {compound}"
+        );
+    }
+}